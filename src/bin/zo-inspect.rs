@@ -0,0 +1,445 @@
+//! Pretty-prints zo accounts from files, enabled with the `inspect`
+//! feature.
+//!
+//! ```text
+//! zo-inspect <FILE>...
+//! ```
+//!
+//! Files hold either raw account data or the output of
+//! `solana account --output json`. The account type is detected from the
+//! Anchor discriminator or the dex account flags. Accounts given together
+//! are used to print each other in big units, e.g. a `Margin` with its
+//! `State` and `Cache`, or a book with its dex market.
+
+use anchor_lang::prelude::Pubkey;
+use fixed::types::I80F48;
+use std::{fs, mem::size_of, process::exit, str::FromStr};
+use zo_abi::{
+    dex::{AccountFlag, Event, Side, Slab, ZoDexMarket},
+    health::collateral_balance,
+    snapshot::load_account,
+    Cache, Control, Margin, SpecialOrders, State, WrappedI80F48,
+};
+
+struct Input {
+    path: String,
+    key: Option<Pubkey>,
+    data: Vec<u8>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Kind {
+    State,
+    Cache,
+    Margin,
+    Control,
+    SpecialOrders,
+    DexMarket,
+    Book,
+    EventQueue,
+    Unknown,
+}
+
+fn read_input(path: &str) -> Result<Input, String> {
+    let raw = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    if raw.iter().find(|x| !x.is_ascii_whitespace()) != Some(&b'{') {
+        return Ok(Input {
+            path: path.into(),
+            key: None,
+            data: raw,
+        });
+    }
+
+    let v: serde_json::Value =
+        serde_json::from_slice(&raw).map_err(|e| format!("{}: {}", path, e))?;
+    let account = v.get("account").unwrap_or(&v);
+    let (data, encoding) = match account["data"].as_array().map(Vec::as_slice) {
+        Some([data, encoding]) => (data.as_str(), encoding.as_str()),
+        _ => (None, None),
+    };
+    let data = match (data, encoding) {
+        (Some(x), Some("base64")) => {
+            base64::decode(x).map_err(|e| format!("{}: {}", path, e))?
+        }
+        (_, Some(e)) => {
+            return Err(format!("{}: unsupported encoding {}", path, e))
+        }
+        _ => return Err(format!("{}: missing account data", path)),
+    };
+
+    Ok(Input {
+        path: path.into(),
+        key: v["pubkey"].as_str().and_then(|x| Pubkey::from_str(x).ok()),
+        data,
+    })
+}
+
+fn detect(data: &[u8]) -> Kind {
+    if load_account::<State>(data).is_some() {
+        return Kind::State;
+    }
+    if load_account::<Cache>(data).is_some() {
+        return Kind::Cache;
+    }
+    if load_account::<Margin>(data).is_some() {
+        return Kind::Margin;
+    }
+    if load_account::<Control>(data).is_some() {
+        return Kind::Control;
+    }
+    if load_account::<SpecialOrders>(data).is_some() {
+        return Kind::SpecialOrders;
+    }
+
+    if data.len() < 13 || data[..5] != *b"serum" {
+        return Kind::Unknown;
+    }
+    let flags = u64::from_le_bytes(data[5..13].try_into().unwrap());
+    let has = |f: AccountFlag| flags & f as u64 != 0;
+
+    if !has(AccountFlag::Initialized) {
+        Kind::Unknown
+    } else if has(AccountFlag::Market) && data.len() == size_of::<ZoDexMarket>()
+    {
+        Kind::DexMarket
+    } else if has(AccountFlag::Bids) != has(AccountFlag::Asks) {
+        Kind::Book
+    } else if has(AccountFlag::EventQueue) {
+        Kind::EventQueue
+    } else {
+        Kind::Unknown
+    }
+}
+
+fn num(x: WrappedI80F48) -> f64 {
+    I80F48::from(x).to_num()
+}
+
+/// Accounts the printed account is interpreted with.
+struct Context<'a> {
+    state: Option<&'a State>,
+    cache: Option<&'a Cache>,
+    markets: Vec<&'a ZoDexMarket>,
+}
+
+fn print_state(x: &State) {
+    let (admin, cache, insurance) = (x.admin, x.cache, x.insurance);
+    println!("admin      {}", admin);
+    println!("cache      {}", cache);
+    println!("insurance  {}", insurance);
+
+    println!("\ncollaterals");
+    for (i, c) in x.collaterals[..x.total_collaterals as usize]
+        .iter()
+        .enumerate()
+    {
+        let (mint, decimals, weight, liq_fee, max_deposit) =
+            (c.mint, c.decimals, c.weight, c.liq_fee, c.max_deposit);
+        println!(
+            "  {:>2} {:<10} {} decimals={} weight={} liq_fee={} borrowable={} swappable={} max_deposit={}",
+            i,
+            c.oracle_symbol.to_string(),
+            mint,
+            decimals,
+            weight,
+            liq_fee,
+            c.is_borrowable,
+            c.is_swappable,
+            max_deposit,
+        );
+    }
+
+    println!("\nperp markets");
+    for (i, m) in x.perp_markets[..x.total_markets as usize]
+        .iter()
+        .enumerate()
+    {
+        let (dex_market, strike, base_imf, liq_fee) =
+            (m.dex_market, m.strike, m.base_imf, m.liq_fee);
+        println!(
+            "  {:>2} {:<12} {:?} oracle={} strike={} base_imf={} liq_fee={} lots={}/{} {}",
+            i,
+            m.symbol.to_string(),
+            m.perp_type,
+            m.oracle_symbol,
+            strike,
+            base_imf,
+            liq_fee,
+            { m.asset_lot_size },
+            { m.quote_lot_size },
+            dex_market,
+        );
+    }
+}
+
+fn print_cache(x: &Cache, cx: &Context) {
+    println!("oracles (big quote per big asset)");
+    for o in x.oracles.iter().filter(|o| !o.symbol.is_nil()) {
+        let adj = 10f64.powi(o.base_decimals as i32 - o.quote_decimals as i32);
+        println!(
+            "  {:<10} price={} twap={} updated={} sources={:?}",
+            o.symbol.to_string(),
+            num(o.price) * adj,
+            num(o.twap) * adj,
+            { o.last_updated },
+            o.sources.iter().map(|s| s.ty).collect::<Vec<_>>(),
+        );
+    }
+
+    println!("\nmarks (smol quote per smol asset)");
+    let n = cx.state.map_or(0, |s| s.total_markets as usize);
+    for (i, m) in x.marks[..n].iter().enumerate() {
+        let symbol = cx.state.unwrap().perp_markets[i].symbol.to_string();
+        let twap = m.twap;
+        println!(
+            "  {:>2} {:<12} price={} twap={} sampled={}",
+            i,
+            symbol,
+            num(m.price),
+            num(twap.cumul_avg),
+            { twap.last_sample_start_time },
+        );
+    }
+    if cx.state.is_none() {
+        println!("  (pass the state to print marks)");
+    }
+
+    println!("\nborrows (smol)");
+    let n = cx.state.map_or(0, |s| s.total_collaterals as usize);
+    for (i, b) in x.borrow_cache[..n].iter().enumerate() {
+        println!(
+            "  {:>2} {:<10} supply={} borrows={} supply_mul={} borrow_mul={} updated={}",
+            i,
+            cx.state.unwrap().collaterals[i].oracle_symbol.to_string(),
+            num(b.supply),
+            num(b.borrows),
+            num(b.supply_multiplier),
+            num(b.borrow_multiplier),
+            { b.last_updated },
+        );
+    }
+}
+
+fn print_margin(x: &Margin, cx: &Context) {
+    let (authority, control) = (x.authority, x.control);
+    println!("authority  {}", authority);
+    println!("control    {}", control);
+
+    let state = match cx.state {
+        Some(s) => s,
+        None => {
+            println!("\n(pass the state to print balances)");
+            return;
+        }
+    };
+
+    println!("\nbalances (big)");
+    for (i, c) in state.collaterals[..state.total_collaterals as usize]
+        .iter()
+        .enumerate()
+    {
+        let balance = match cx.cache {
+            Some(cache) => collateral_balance(cache, x, i),
+            None => I80F48::from(x.collateral[i]),
+        };
+        let balance = balance.to_num::<f64>() / 10f64.powi(c.decimals as i32);
+        if balance != 0. {
+            println!("  {:<10} {}", c.oracle_symbol.to_string(), balance);
+        }
+    }
+    if cx.cache.is_none() {
+        println!("  (without interest, pass the cache to apply it)");
+    }
+}
+
+fn print_control(x: &Control, cx: &Context) {
+    let authority = x.authority;
+    println!("authority  {}", authority);
+
+    println!("\npositions (smol)");
+    for (i, oo) in x.open_orders_agg.iter().enumerate() {
+        if oo.key == Pubkey::default() {
+            continue;
+        }
+        let symbol = match cx.state {
+            Some(s) => s.perp_markets[i].symbol.to_string(),
+            None => format!("#{}", i),
+        };
+        println!(
+            "  {:<12} {} pos_size={} native_pc_total={} realized_pnl={} on_bids={} on_asks={} orders={}",
+            symbol,
+            { oo.key },
+            { oo.pos_size },
+            { oo.native_pc_total },
+            { oo.realized_pnl },
+            { oo.coin_on_bids },
+            { oo.coin_on_asks },
+            oo.order_count,
+        );
+    }
+}
+
+fn print_special_orders(x: &SpecialOrders) {
+    let (authority, prev_id) = (x.authority, x.prev_id);
+    println!("authority  {}", authority);
+    println!("prev_id    {}", prev_id);
+
+    println!("\nentries (prices in smol per big, size in lots)");
+    for o in x.iter() {
+        println!(
+            "  {:>5} {:?} {} trigger={} limit={} size={} fee={} {}",
+            { o.id },
+            o.ty,
+            if o.is_long { "long" } else { "short" },
+            { o.trigger_price },
+            { o.limit_price },
+            { o.size },
+            { o.fee },
+            { o.market },
+        );
+    }
+}
+
+fn print_dex_market(x: &ZoDexMarket) {
+    println!("own_address   {}", { x.own_address });
+    println!("bids          {}", { x.bids });
+    println!("asks          {}", { x.asks });
+    println!("event_q       {}", { x.event_q });
+    println!("req_q         {}", { x.req_q });
+    println!("lot sizes     coin={} pc={}", { x.coin_lot_size }, {
+        x.pc_lot_size
+    });
+    println!("coin_decimals {}", { x.coin_decimals });
+    println!("perp_type     {}", { x.perp_type });
+    println!("strike        {}", { x.strike });
+    println!("funding_index {}", { x.funding_index });
+    println!("last_updated  {}", { x.last_updated });
+    println!("open_interest {}", { x.open_interest });
+}
+
+fn print_book(key: Option<Pubkey>, data: &[u8], cx: &Context) {
+    if !Slab::is_valid(data) {
+        return println!("invalid book: bad flags, padding or nodes");
+    }
+    let slab = match Slab::deserialize(data) {
+        Ok(x) => x,
+        Err(e) => return println!("invalid book: {:?}", e),
+    };
+    let market = key
+        .and_then(|k| cx.markets.iter().find(|m| m.bids == k || m.asks == k));
+    let side = match slab.is_bids() {
+        true => Side::Bid,
+        false => Side::Ask,
+    };
+
+    println!("{:?}s, best first", side);
+    for n in slab.iter_best() {
+        match market {
+            Some(m) => {
+                let o = m.parse_order(n, side);
+                println!(
+                    "  {:>14} x {:<14} {} client_id={}",
+                    o.price, o.size, o.control, o.client_order_id
+                );
+            }
+            None => println!(
+                "  {:>14} x {:<14} {} client_id={} (lots)",
+                n.price(),
+                { n.quantity },
+                { n.control },
+                { n.client_order_id },
+            ),
+        }
+    }
+}
+
+fn print_event_queue(data: &[u8]) {
+    if !Event::is_valid_queue(data) {
+        return println!("invalid event queue: bad flags, padding or head");
+    }
+    let (header, events) = match Event::deserialize_queue(data) {
+        Ok(x) => x,
+        Err(e) => return println!("invalid event queue: {:?}", e),
+    };
+    println!(
+        "head={} count={} seq_num={}",
+        { header.head },
+        { header.count },
+        { header.seq_num }
+    );
+
+    for e in events {
+        println!(
+            "  {} {} {} released={} paid={} fee={} order_id={} {}",
+            if e.is_fill() { "fill" } else { "out " },
+            if e.is_bid() { "bid" } else { "ask" },
+            if e.is_maker() { "maker" } else { "taker" },
+            { e.native_qty_released },
+            { e.native_qty_paid },
+            { e.native_fee_or_rebate },
+            { e.order_id },
+            { e.control },
+        );
+    }
+}
+
+fn main() {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() || paths.iter().any(|x| x == "-h" || x == "--help") {
+        eprintln!("usage: zo-inspect <FILE>...");
+        exit(2);
+    }
+
+    let inputs: Vec<Input> = paths
+        .iter()
+        .map(|p| read_input(p))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            exit(1);
+        });
+    let kinds: Vec<Kind> = inputs.iter().map(|x| detect(&x.data)).collect();
+
+    let of_kind = |k: Kind| {
+        inputs
+            .iter()
+            .zip(kinds.iter())
+            .filter(move |(_, x)| **x == k)
+            .map(|(x, _)| x.data.as_slice())
+    };
+    let cx = Context {
+        state: of_kind(Kind::State).find_map(load_account::<State>),
+        cache: of_kind(Kind::Cache).find_map(load_account::<Cache>),
+        markets: of_kind(Kind::DexMarket)
+            .filter_map(|x| bytemuck::try_from_bytes(x).ok())
+            .collect(),
+    };
+
+    for (i, (input, kind)) in inputs.iter().zip(kinds.iter()).enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match input.key {
+            Some(k) => println!("== {} {} ({:?})", input.path, k, kind),
+            None => println!("== {} ({:?})", input.path, kind),
+        }
+
+        let data = input.data.as_slice();
+        match kind {
+            Kind::State => print_state(load_account(data).unwrap()),
+            Kind::Cache => print_cache(load_account(data).unwrap(), &cx),
+            Kind::Margin => print_margin(load_account(data).unwrap(), &cx),
+            Kind::Control => print_control(load_account(data).unwrap(), &cx),
+            Kind::SpecialOrders => {
+                print_special_orders(load_account(data).unwrap())
+            }
+            Kind::DexMarket => print_dex_market(bytemuck::from_bytes(data)),
+            Kind::Book => print_book(input.key, data, &cx),
+            Kind::EventQueue => print_event_queue(data),
+            Kind::Unknown => {
+                println!("unknown account of {} bytes", data.len())
+            }
+        }
+    }
+}
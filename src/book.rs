@@ -0,0 +1,132 @@
+//! Incremental updates between two snapshots of one side of a book.
+//!
+//! Prices and quantities are in lots, see `ZoDexMarket::lots_to_price`
+//! and `ZoDexMarket::lots_to_size`.
+
+use crate::dex::{LeafNode, Slab};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Change of an order, keyed by `LeafNode.key`.
+#[derive(Copy, Clone, Debug)]
+pub enum L3Change {
+    Added(LeafNode),
+    Removed(LeafNode),
+    /// The order was partially filled.
+    SizeChanged {
+        before: LeafNode,
+        after: LeafNode,
+    },
+}
+
+impl L3Change {
+    pub fn key(&self) -> u128 {
+        match self {
+            Self::Added(x) | Self::Removed(x) => x.key,
+            Self::SizeChanged { after, .. } => after.key,
+        }
+    }
+}
+
+/// Total quantity at a price.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct L2Level {
+    pub price: u64,
+    pub quantity: u64,
+}
+
+/// Change of the quantity at a price, zero when the level is empty.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct L2Change {
+    pub price: u64,
+    pub before: u64,
+    pub after: u64,
+}
+
+fn check_sides(before: &Slab, after: &Slab) {
+    assert!(
+        before.is_bids() == after.is_bids(),
+        "Cannot diff slabs of different sides"
+    );
+}
+
+/// Removed, resized, then added orders, each best price first.
+pub fn l3_diff(before: &Slab, after: &Slab) -> Vec<L3Change> {
+    check_sides(before, after);
+
+    let b: BTreeMap<u128, &LeafNode> =
+        before.iter_best().map(|x| (x.key, x)).collect();
+    let a: BTreeMap<u128, &LeafNode> =
+        after.iter_best().map(|x| (x.key, x)).collect();
+
+    let removed = before
+        .iter_best()
+        .filter(|x| !a.contains_key(&{ x.key }))
+        .map(|x| L3Change::Removed(*x));
+    let changed = after.iter_best().filter_map(|x| {
+        let y = b.get(&{ x.key })?;
+        ({ x.quantity } != { y.quantity }).then_some(L3Change::SizeChanged {
+            before: **y,
+            after: *x,
+        })
+    });
+    let added = after
+        .iter_best()
+        .filter(|x| !b.contains_key(&{ x.key }))
+        .map(|x| L3Change::Added(*x));
+
+    removed.chain(changed).chain(added).collect()
+}
+
+/// Levels of the book, best first.
+pub fn l2_levels(slab: &Slab) -> Vec<L2Level> {
+    let mut r: Vec<L2Level> = Vec::new();
+
+    for x in slab.iter_best() {
+        let price = x.price().get();
+        match r.last_mut() {
+            Some(l) if l.price == price => l.quantity += x.quantity,
+            _ => r.push(L2Level {
+                price,
+                quantity: x.quantity,
+            }),
+        }
+    }
+
+    r
+}
+
+/// Levels whose quantity changed, best price first.
+pub fn l2_diff(before: &Slab, after: &Slab) -> Vec<L2Change> {
+    check_sides(before, after);
+
+    let b: BTreeMap<u64, u64> = l2_levels(before)
+        .into_iter()
+        .map(|l| (l.price, l.quantity))
+        .collect();
+    let a: BTreeMap<u64, u64> = l2_levels(after)
+        .into_iter()
+        .map(|l| (l.price, l.quantity))
+        .collect();
+
+    let mut r: Vec<L2Change> = b
+        .keys()
+        .chain(a.keys())
+        .copied()
+        .collect::<BTreeSet<u64>>()
+        .into_iter()
+        .filter_map(|price| {
+            let before = b.get(&price).copied().unwrap_or(0);
+            let after = a.get(&price).copied().unwrap_or(0);
+            (before != after).then_some(L2Change {
+                price,
+                before,
+                after,
+            })
+        })
+        .collect();
+
+    if after.is_bids() {
+        r.reverse();
+    }
+    r
+}
@@ -0,0 +1,676 @@
+//! Wrappers around the `cpi` module for programs whose PDAs own zo
+//! margins.
+//!
+//! `ZoCpi` bundles the accounts shared by every margin instruction, and
+//! signs each call with the authority's seeds.
+//!
+//! ```ignore
+//! let seeds: &[&[&[u8]]] = &[&[b"vault", &[vault.bump]]];
+//! let zo = ZoCpi::new(
+//!     zo_program, &zo_state, zo_state_signer, &zo_cache,
+//!     vault_authority, &zo_margin, &zo_control, seeds,
+//! )?;
+//! zo.deposit(token_account, zo_vault, token_program, false, amount)?;
+//! ```
+//!
+//! Compute grows with the number of collaterals and markets, and
+//! matching with the number of orders taken, so simulate the transaction
+//! to size the compute budget request. Every PDA checked by a wrapper
+//! adds a `find_program_address`.
+//!
+//! Keeper instructions take no margin or signer, so they are called
+//! through `cpi` directly.
+
+use crate::{
+    cpi::{self, accounts},
+    margin_address, open_orders_address,
+    special_orders::SpecialOrderParams,
+    special_orders_address, state_signer_address, Cache, Control, Margin,
+    OrderType, SpecialOrders, State,
+};
+use anchor_lang::{error::ErrorCode, prelude::*};
+
+/// Zo dex accounts of a perp market and the authority's open orders.
+#[derive(Clone)]
+pub struct DexAccounts<'info> {
+    pub open_orders: AccountInfo<'info>,
+    pub dex_market: AccountInfo<'info>,
+    pub req_q: AccountInfo<'info>,
+    pub event_q: AccountInfo<'info>,
+    pub market_bids: AccountInfo<'info>,
+    pub market_asks: AccountInfo<'info>,
+    pub dex_program: AccountInfo<'info>,
+}
+
+/// Arguments shared by the `place_perp_order` variants.
+#[derive(Copy, Clone)]
+pub struct PerpOrderArgs {
+    pub is_long: bool,
+    /// In price lots.
+    pub limit_price: u64,
+    /// In base lots.
+    pub max_base_quantity: u64,
+    /// In smol quote.
+    pub max_quote_quantity: u64,
+    pub order_type: OrderType,
+    pub limit: u16,
+    pub client_id: u64,
+}
+
+/// Accounts of the margin being liquidated. `open_orders` is only used
+/// by perp liquidations, and is checked by zo rather than here.
+#[derive(Clone)]
+pub struct LiqeeAccounts<'info> {
+    pub authority: AccountInfo<'info>,
+    pub margin: AccountInfo<'info>,
+    pub control: AccountInfo<'info>,
+    pub open_orders: AccountInfo<'info>,
+}
+
+/// Serum accounts of the `swap` instruction, see `crate::serum`.
+#[derive(Clone)]
+pub struct SwapAccounts<'info> {
+    pub quote_mint: AccountInfo<'info>,
+    pub quote_vault: AccountInfo<'info>,
+    pub asset_mint: AccountInfo<'info>,
+    pub asset_vault: AccountInfo<'info>,
+    pub swap_fee_vault: AccountInfo<'info>,
+    pub serum_open_orders: AccountInfo<'info>,
+    pub serum_market: AccountInfo<'info>,
+    pub serum_request_queue: AccountInfo<'info>,
+    pub serum_event_queue: AccountInfo<'info>,
+    pub serum_bids: AccountInfo<'info>,
+    pub serum_asks: AccountInfo<'info>,
+    pub serum_coin_vault: AccountInfo<'info>,
+    pub serum_pc_vault: AccountInfo<'info>,
+    pub serum_vault_signer: AccountInfo<'info>,
+    pub srm_spot_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+}
+
+/// Calls `create_margin` for a PDA authority, deriving `margin_nonce`
+/// from the margin address. The payer must not be a PDA.
+pub fn create_margin<'info>(
+    zo_program: AccountInfo<'info>,
+    accounts: accounts::CreateMargin<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let (margin, nonce) =
+        margin_address(accounts.authority.key, accounts.state.key);
+    require_keys_eq!(margin, *accounts.margin.key, ErrorCode::ConstraintSeeds);
+
+    cpi::create_margin(
+        CpiContext::new_with_signer(zo_program, accounts, signer_seeds),
+        nonce,
+    )
+}
+
+/// Accounts of a margin owned by a PDA, with the seeds it signs with.
+pub struct ZoCpi<'a, 'info> {
+    zo_program: AccountInfo<'info>,
+    state: &'a AccountLoader<'info, State>,
+    state_signer: AccountInfo<'info>,
+    cache: &'a AccountLoader<'info, Cache>,
+    authority: AccountInfo<'info>,
+    margin: &'a AccountLoader<'info, Margin>,
+    control: &'a AccountLoader<'info, Control>,
+    signer_seeds: &'a [&'a [&'a [u8]]],
+}
+
+impl<'a, 'info> ZoCpi<'a, 'info> {
+    /// Checks `state_signer` and `margin` against their seeds, which costs
+    /// two `find_program_address`, so build this once per instruction.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        zo_program: AccountInfo<'info>,
+        state: &'a AccountLoader<'info, State>,
+        state_signer: AccountInfo<'info>,
+        cache: &'a AccountLoader<'info, Cache>,
+        authority: AccountInfo<'info>,
+        margin: &'a AccountLoader<'info, Margin>,
+        control: &'a AccountLoader<'info, Control>,
+        signer_seeds: &'a [&'a [&'a [u8]]],
+    ) -> Result<Self> {
+        require_keys_eq!(
+            zo_program.key(),
+            crate::ID,
+            ErrorCode::InvalidProgramId
+        );
+        require_keys_eq!(
+            state_signer_address(&state.key()).0,
+            state_signer.key(),
+            ErrorCode::ConstraintSeeds
+        );
+        require_keys_eq!(
+            margin_address(authority.key, &state.key()).0,
+            margin.key(),
+            ErrorCode::ConstraintSeeds
+        );
+
+        Ok(Self {
+            zo_program,
+            state,
+            state_signer,
+            cache,
+            authority,
+            margin,
+            control,
+            signer_seeds,
+        })
+    }
+
+    /// Checks the authority's open orders account on `dex_market`.
+    fn check_open_orders(
+        &self,
+        open_orders: &AccountInfo<'info>,
+        dex_market: &AccountInfo<'info>,
+    ) -> Result<()> {
+        require_keys_eq!(
+            open_orders_address(&self.control.key(), dex_market.key).0,
+            open_orders.key(),
+            ErrorCode::ConstraintSeeds
+        );
+        Ok(())
+    }
+
+    fn check_special_orders(&self, special_orders: &Pubkey) -> Result<()> {
+        require_keys_eq!(
+            special_orders_address(self.authority.key, &self.state.key()).0,
+            *special_orders,
+            ErrorCode::ConstraintSeeds
+        );
+        Ok(())
+    }
+
+    fn ctx<T: ToAccountMetas + ToAccountInfos<'info>>(
+        &self,
+        accounts: T,
+    ) -> CpiContext<'a, 'a, 'a, 'info, T> {
+        CpiContext::new_with_signer(
+            self.zo_program.clone(),
+            accounts,
+            self.signer_seeds,
+        )
+    }
+
+    pub fn deposit(
+        &self,
+        token_account: AccountInfo<'info>,
+        vault: AccountInfo<'info>,
+        token_program: AccountInfo<'info>,
+        repay_only: bool,
+        amount: u64,
+    ) -> Result<()> {
+        let accounts = accounts::Deposit {
+            state: self.state.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            cache: self.cache.to_account_info(),
+            authority: self.authority.clone(),
+            margin: self.margin.to_account_info(),
+            token_account,
+            vault,
+            token_program,
+        };
+        cpi::deposit(self.ctx(accounts), repay_only, amount)
+    }
+
+    /// `heimdall` is `ZO_HEIMDALL_ID`. Checks the margin's health.
+    pub fn withdraw(
+        &self,
+        token_account: AccountInfo<'info>,
+        vault: AccountInfo<'info>,
+        token_program: AccountInfo<'info>,
+        heimdall: AccountInfo<'info>,
+        allow_borrow: bool,
+        amount: u64,
+    ) -> Result<()> {
+        let accounts = accounts::Withdraw {
+            state: self.state.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            cache: self.cache.to_account_info(),
+            authority: self.authority.clone(),
+            margin: self.margin.to_account_info(),
+            control: self.control.to_account_info(),
+            token_account,
+            vault,
+            token_program,
+            heimdall,
+        };
+        cpi::withdraw(self.ctx(accounts), allow_borrow, amount)
+    }
+
+    /// The payer must not be a PDA. Checks `open_orders`.
+    pub fn create_perp_open_orders(
+        &self,
+        payer: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        dex_market: AccountInfo<'info>,
+        dex_program: AccountInfo<'info>,
+        rent: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        self.check_open_orders(&open_orders, &dex_market)?;
+        let accounts = accounts::CreatePerpOpenOrders {
+            state: self.state.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            authority: self.authority.clone(),
+            payer,
+            margin: self.margin.to_account_info(),
+            control: self.control.to_account_info(),
+            open_orders,
+            dex_market,
+            dex_program,
+            rent,
+            system_program,
+        };
+        cpi::create_perp_open_orders(self.ctx(accounts))
+    }
+
+    fn place_perp_order_accounts(
+        &self,
+        dex: &DexAccounts<'info>,
+        rent: AccountInfo<'info>,
+    ) -> Result<accounts::PlacePerpOrder<'info>> {
+        self.check_open_orders(&dex.open_orders, &dex.dex_market)?;
+        Ok(accounts::PlacePerpOrder {
+            state: self.state.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            cache: self.cache.to_account_info(),
+            authority: self.authority.clone(),
+            margin: self.margin.to_account_info(),
+            control: self.control.to_account_info(),
+            open_orders: dex.open_orders.clone(),
+            dex_market: dex.dex_market.clone(),
+            req_q: dex.req_q.clone(),
+            event_q: dex.event_q.clone(),
+            market_bids: dex.market_bids.clone(),
+            market_asks: dex.market_asks.clone(),
+            dex_program: dex.dex_program.clone(),
+            rent,
+        })
+    }
+
+    /// Settles funds after matching. Use `place_perp_order_lite` to save
+    /// compute when that is not needed. Checks `dex.open_orders`. Compute
+    /// grows with the orders matched, up to `limit`.
+    pub fn place_perp_order(
+        &self,
+        dex: &DexAccounts<'info>,
+        rent: AccountInfo<'info>,
+        args: PerpOrderArgs,
+    ) -> Result<()> {
+        let accounts = self.place_perp_order_accounts(dex, rent)?;
+        cpi::place_perp_order(
+            self.ctx(accounts),
+            args.is_long,
+            args.limit_price,
+            args.max_base_quantity,
+            args.max_quote_quantity,
+            args.order_type,
+            args.limit,
+            args.client_id,
+        )
+    }
+
+    /// As `place_perp_order`, failing once the clock passes `max_ts`.
+    pub fn place_perp_order_with_max_ts(
+        &self,
+        dex: &DexAccounts<'info>,
+        rent: AccountInfo<'info>,
+        args: PerpOrderArgs,
+        max_ts: i64,
+    ) -> Result<()> {
+        let accounts = self.place_perp_order_accounts(dex, rent)?;
+        cpi::place_perp_order_with_max_ts(
+            self.ctx(accounts),
+            args.is_long,
+            args.limit_price,
+            args.max_base_quantity,
+            args.max_quote_quantity,
+            args.order_type,
+            args.limit,
+            args.client_id,
+            max_ts,
+        )
+    }
+
+    /// Uses less compute than `place_perp_order`, but does not settle
+    /// funds. Currently only available on devnet. Checks
+    /// `dex.open_orders`. Compute grows with the orders matched, up to
+    /// `limit`.
+    pub fn place_perp_order_lite(
+        &self,
+        dex: &DexAccounts<'info>,
+        rent: AccountInfo<'info>,
+        args: PerpOrderArgs,
+    ) -> Result<()> {
+        let accounts = self.place_perp_order_accounts(dex, rent)?;
+        cpi::place_perp_order_lite(
+            self.ctx(accounts),
+            args.is_long,
+            args.limit_price,
+            args.max_base_quantity,
+            args.max_quote_quantity,
+            args.order_type,
+            args.limit,
+            args.client_id,
+        )
+    }
+
+    /// Checks `dex.open_orders`.
+    pub fn cancel_perp_order(
+        &self,
+        dex: &DexAccounts<'info>,
+        order_id: Option<u128>,
+        is_long: Option<bool>,
+        client_id: Option<u64>,
+    ) -> Result<()> {
+        self.check_open_orders(&dex.open_orders, &dex.dex_market)?;
+        let accounts = accounts::CancelPerpOrder {
+            state: self.state.to_account_info(),
+            cache: self.cache.to_account_info(),
+            authority: self.authority.clone(),
+            margin: self.margin.to_account_info(),
+            control: self.control.to_account_info(),
+            open_orders: dex.open_orders.clone(),
+            dex_market: dex.dex_market.clone(),
+            market_bids: dex.market_bids.clone(),
+            market_asks: dex.market_asks.clone(),
+            event_q: dex.event_q.clone(),
+            dex_program: dex.dex_program.clone(),
+        };
+        cpi::cancel_perp_order(self.ctx(accounts), order_id, is_long, client_id)
+    }
+
+    /// Checks `dex.open_orders`. Compute grows with the orders cancelled,
+    /// up to `limit`.
+    pub fn cancel_all_perp_orders(
+        &self,
+        dex: &DexAccounts<'info>,
+        limit: u16,
+    ) -> Result<()> {
+        self.check_open_orders(&dex.open_orders, &dex.dex_market)?;
+        let accounts = accounts::CancelAllPerpOrders {
+            authority: self.authority.clone(),
+            state: self.state.to_account_info(),
+            cache: self.cache.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            margin: self.margin.to_account_info(),
+            control: self.control.to_account_info(),
+            open_orders: dex.open_orders.clone(),
+            dex_market: dex.dex_market.clone(),
+            req_q: dex.req_q.clone(),
+            event_q: dex.event_q.clone(),
+            market_bids: dex.market_bids.clone(),
+            market_asks: dex.market_asks.clone(),
+            dex_program: dex.dex_program.clone(),
+        };
+        cpi::cancel_all_perp_orders(self.ctx(accounts), limit)
+    }
+
+    /// Checks `open_orders`.
+    pub fn settle_funds(
+        &self,
+        open_orders: AccountInfo<'info>,
+        dex_market: AccountInfo<'info>,
+        dex_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        self.check_open_orders(&open_orders, &dex_market)?;
+        let accounts = accounts::SettleFunds {
+            authority: self.authority.clone(),
+            state: self.state.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            cache: self.cache.to_account_info(),
+            margin: self.margin.to_account_info(),
+            control: self.control.to_account_info(),
+            open_orders,
+            dex_market,
+            dex_program,
+        };
+        cpi::settle_funds(self.ctx(accounts))
+    }
+
+    /// See `swap::quote_swap` for `min_rate`. Checks the margin's health
+    /// and matches on serum.
+    pub fn swap(
+        &self,
+        serum: SwapAccounts<'info>,
+        buy: bool,
+        allow_borrow: bool,
+        amount: u64,
+        min_rate: u64,
+    ) -> Result<()> {
+        let accounts = accounts::Swap {
+            authority: self.authority.clone(),
+            state: self.state.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            cache: self.cache.to_account_info(),
+            margin: self.margin.to_account_info(),
+            control: self.control.to_account_info(),
+            quote_mint: serum.quote_mint,
+            quote_vault: serum.quote_vault,
+            asset_mint: serum.asset_mint,
+            asset_vault: serum.asset_vault,
+            swap_fee_vault: serum.swap_fee_vault,
+            serum_open_orders: serum.serum_open_orders,
+            serum_market: serum.serum_market,
+            serum_request_queue: serum.serum_request_queue,
+            serum_event_queue: serum.serum_event_queue,
+            serum_bids: serum.serum_bids,
+            serum_asks: serum.serum_asks,
+            serum_coin_vault: serum.serum_coin_vault,
+            serum_pc_vault: serum.serum_pc_vault,
+            serum_vault_signer: serum.serum_vault_signer,
+            srm_spot_program: serum.srm_spot_program,
+            token_program: serum.token_program,
+            rent: serum.rent,
+        };
+        cpi::swap(self.ctx(accounts), buy, allow_borrow, amount, min_rate)
+    }
+
+    /// The payer must not be a PDA. Checks `special_orders`.
+    pub fn create_special_orders_account(
+        &self,
+        payer: AccountInfo<'info>,
+        special_orders: AccountInfo<'info>,
+        rent: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        self.check_special_orders(special_orders.key)?;
+        let accounts = accounts::CreateSpecialOrdersAccount {
+            state: self.state.to_account_info(),
+            authority: self.authority.clone(),
+            payer,
+            special_orders,
+            rent,
+            system_program,
+        };
+        cpi::create_special_orders_account(self.ctx(accounts))
+    }
+
+    /// See `special_orders::validate_special_order` for `params`. Checks
+    /// `special_orders`.
+    pub fn place_special_order(
+        &self,
+        special_orders: &AccountLoader<'info, SpecialOrders>,
+        dex_market: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+        params: SpecialOrderParams,
+    ) -> Result<()> {
+        self.check_special_orders(&special_orders.key())?;
+        let accounts = accounts::PlaceSpecialOrder {
+            state: self.state.to_account_info(),
+            authority: self.authority.clone(),
+            special_orders: special_orders.to_account_info(),
+            dex_market,
+            system_program,
+        };
+        cpi::place_special_order(
+            self.ctx(accounts),
+            params.is_long,
+            params.ty,
+            params.trigger_price,
+            params.limit_price,
+            params.size,
+        )
+    }
+
+    /// Checks `special_orders`.
+    pub fn cancel_special_order(
+        &self,
+        special_orders: &AccountLoader<'info, SpecialOrders>,
+        dex_market: AccountInfo<'info>,
+        id: u16,
+    ) -> Result<()> {
+        self.check_special_orders(&special_orders.key())?;
+        let accounts = accounts::CancelSpecialOrder {
+            state: self.state.to_account_info(),
+            authority: self.authority.clone(),
+            special_orders: special_orders.to_account_info(),
+            dex_market,
+        };
+        cpi::cancel_special_order(self.ctx(accounts), id)
+    }
+
+    /// Executes one of the authority's own special orders, paying the
+    /// execution fee to `payer`. Checks `special_orders` and
+    /// `dex.open_orders`. Places the order, so compute grows with the
+    /// orders matched.
+    pub fn execute_special_order(
+        &self,
+        payer: AccountInfo<'info>,
+        special_orders: &AccountLoader<'info, SpecialOrders>,
+        dex: &DexAccounts<'info>,
+        rent: AccountInfo<'info>,
+        id: u16,
+    ) -> Result<()> {
+        self.check_special_orders(&special_orders.key())?;
+        self.check_open_orders(&dex.open_orders, &dex.dex_market)?;
+        let accounts = accounts::ExecuteSpecialOrder {
+            state: self.state.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            cache: self.cache.to_account_info(),
+            payer,
+            authority: self.authority.clone(),
+            margin: self.margin.to_account_info(),
+            control: self.control.to_account_info(),
+            special_orders: special_orders.to_account_info(),
+            open_orders: dex.open_orders.clone(),
+            dex_market: dex.dex_market.clone(),
+            req_q: dex.req_q.clone(),
+            event_q: dex.event_q.clone(),
+            market_bids: dex.market_bids.clone(),
+            market_asks: dex.market_asks.clone(),
+            dex_program: dex.dex_program.clone(),
+            rent,
+        };
+        cpi::execute_special_order(self.ctx(accounts), id)
+    }
+
+    /// Cancels the orders of `liqee` on the market, with the authority as
+    /// the pruner. Compute grows with the orders cancelled, up to `limit`.
+    pub fn force_cancel_all_perp_orders(
+        &self,
+        liqee: &LiqeeAccounts<'info>,
+        dex: &DexAccounts<'info>,
+        limit: u16,
+    ) -> Result<()> {
+        let accounts = accounts::ForceCancelAllPerpOrders {
+            pruner: self.authority.clone(),
+            state: self.state.to_account_info(),
+            cache: self.cache.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            liqee_margin: liqee.margin.clone(),
+            liqee_control: liqee.control.clone(),
+            liqee_oo: liqee.open_orders.clone(),
+            dex_market: dex.dex_market.clone(),
+            req_q: dex.req_q.clone(),
+            event_q: dex.event_q.clone(),
+            market_bids: dex.market_bids.clone(),
+            market_asks: dex.market_asks.clone(),
+            dex_program: dex.dex_program.clone(),
+        };
+        cpi::force_cancel_all_perp_orders(self.ctx(accounts), limit)
+    }
+
+    /// Takes over `asset_transfer_lots` of the liqee's position, with the
+    /// margin as the liqor. Checks `dex.open_orders`, the liqor's. The
+    /// program checks the health of both margins and cancels the liqee's
+    /// orders, so it usually needs a larger compute budget.
+    pub fn liquidate_perp_position(
+        &self,
+        liqee: &LiqeeAccounts<'info>,
+        dex: &DexAccounts<'info>,
+        asset_transfer_lots: u64,
+    ) -> Result<()> {
+        self.check_open_orders(&dex.open_orders, &dex.dex_market)?;
+        let accounts = accounts::LiquidatePerpPosition {
+            state: self.state.to_account_info(),
+            cache: self.cache.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            liqor: self.authority.clone(),
+            liqor_margin: self.margin.to_account_info(),
+            liqor_control: self.control.to_account_info(),
+            liqor_oo: dex.open_orders.clone(),
+            liqee: liqee.authority.clone(),
+            liqee_margin: liqee.margin.clone(),
+            liqee_control: liqee.control.clone(),
+            liqee_oo: liqee.open_orders.clone(),
+            dex_market: dex.dex_market.clone(),
+            req_q: dex.req_q.clone(),
+            event_q: dex.event_q.clone(),
+            market_bids: dex.market_bids.clone(),
+            market_asks: dex.market_asks.clone(),
+            dex_program: dex.dex_program.clone(),
+        };
+        cpi::liquidate_perp_position(self.ctx(accounts), asset_transfer_lots)
+    }
+
+    /// Takes over `asset_transfer_amount` smol of the liqee's
+    /// `asset_mint` borrows or deposits against `quote_mint`, with the
+    /// margin as the liqor. Checks the health of both margins, so it
+    /// usually needs a larger compute budget.
+    pub fn liquidate_spot_position(
+        &self,
+        liqee: &LiqeeAccounts<'info>,
+        asset_mint: AccountInfo<'info>,
+        quote_mint: AccountInfo<'info>,
+        asset_transfer_amount: i64,
+    ) -> Result<()> {
+        let accounts = accounts::LiquidateSpotPosition {
+            state: self.state.to_account_info(),
+            cache: self.cache.to_account_info(),
+            liqor: self.authority.clone(),
+            liqor_margin: self.margin.to_account_info(),
+            liqor_control: self.control.to_account_info(),
+            liqee_margin: liqee.margin.clone(),
+            liqee_control: liqee.control.clone(),
+            asset_mint,
+            quote_mint,
+        };
+        cpi::liquidate_spot_position(self.ctx(accounts), asset_transfer_amount)
+    }
+
+    /// Takes over the bankrupt liqee's `asset_mint` borrows, with the
+    /// margin as the liqor. Checks the health of both margins, so it
+    /// usually needs a larger compute budget.
+    pub fn settle_bankruptcy(
+        &self,
+        liqee: &LiqeeAccounts<'info>,
+        asset_mint: AccountInfo<'info>,
+    ) -> Result<()> {
+        let accounts = accounts::SettleBankruptcy {
+            state: self.state.to_account_info(),
+            state_signer: self.state_signer.clone(),
+            cache: self.cache.to_account_info(),
+            liqor: self.authority.clone(),
+            liqor_margin: self.margin.to_account_info(),
+            liqor_control: self.control.to_account_info(),
+            liqee_margin: liqee.margin.clone(),
+            liqee_control: liqee.control.clone(),
+            asset_mint,
+        };
+        cpi::settle_bankruptcy(self.ctx(accounts))
+    }
+}
@@ -0,0 +1,197 @@
+//! Instructions for the keeper cranks, with their remaining accounts.
+
+use crate::{
+    dex::{Event, ZoDexMarket},
+    open_orders_address, state_signer_address, Cache, OracleType, State,
+    Symbol, ZO_DEX_PID,
+};
+use anchor_lang::{prelude::Pubkey, InstructionData, ToAccountMetas};
+use bytemuck::PodCastError;
+use solana_program::instruction::{AccountMeta, Instruction};
+
+/// Accounts that fit in a legacy transaction alongside a single
+/// instruction's data.
+pub const MAX_TX_ACCOUNTS: usize = 32;
+
+/// Fee payer and zo program, present in every transaction.
+const TX_OVERHEAD_ACCOUNTS: usize = 2;
+
+/// Unique `control` keys of the events in the queue, in queue order.
+pub fn event_queue_controls(buf: &[u8]) -> Result<Vec<Pubkey>, PodCastError> {
+    let (_, events) = Event::deserialize_queue(buf)?;
+    let mut r: Vec<Pubkey> = Vec::new();
+
+    for e in events {
+        let control = e.control;
+        if !r.contains(&control) {
+            r.push(control);
+        }
+    }
+
+    Ok(r)
+}
+
+/// Number of users per instruction when each takes `per_user` remaining
+/// accounts next to `fixed` named ones.
+fn chunk_size(max_accounts: usize, fixed: usize, per_user: usize) -> usize {
+    max_accounts
+        .saturating_sub(fixed + TX_OVERHEAD_ACCOUNTS)
+        .checked_div(per_user)
+        .unwrap_or(0)
+        .max(1)
+}
+
+fn writable(keys: impl Iterator<Item = Pubkey>) -> Vec<AccountMeta> {
+    keys.map(|k| AccountMeta::new(k, false)).collect()
+}
+
+/// `consume_events` instructions for the given controls, split so each
+/// transaction stays within `max_accounts`. Remaining accounts are
+/// `[alice_control, bob_control, ..., alice_oo, bob_oo, ...]`.
+///
+/// The dex consumes events in queue order and stops at the first event
+/// whose control is missing, so only the first instruction is sure to
+/// make progress. Pass `controls` in queue order, as from
+/// `event_queue_controls`, and treat the later instructions as retries:
+/// each consumes nothing until those before it have drained the events
+/// in front of its controls.
+pub fn consume_events_ixs(
+    state: &Pubkey,
+    market: &ZoDexMarket,
+    controls: &[Pubkey],
+    limit: u16,
+    max_accounts: usize,
+) -> Vec<Instruction> {
+    let accounts = crate::accounts::ConsumeEvents {
+        state: *state,
+        state_signer: state_signer_address(state).0,
+        dex_program: ZO_DEX_PID,
+        market: market.own_address,
+        event_queue: market.event_q,
+    };
+    let n = chunk_size(max_accounts, 5, 2);
+
+    controls
+        .chunks(n)
+        .map(|chunk| {
+            let oo = chunk
+                .iter()
+                .map(|c| open_orders_address(c, &market.own_address).0);
+
+            let mut metas = accounts.to_account_metas(None);
+            metas.extend(writable(chunk.iter().copied()));
+            metas.extend(writable(oo));
+
+            Instruction {
+                program_id: crate::ID,
+                accounts: metas,
+                data: crate::instruction::ConsumeEvents { limit }.data(),
+            }
+        })
+        .collect()
+}
+
+/// `crank_pnl` instructions for the given controls, split so each
+/// transaction stays within `max_accounts`. `margin_of` maps a control to
+/// its margin account; controls it does not know are skipped. Remaining
+/// accounts are `[alice_control, ..., alice_oo, ..., alice_margin, ...]`.
+pub fn crank_pnl_ixs(
+    state: &Pubkey,
+    cache: &Pubkey,
+    market: &ZoDexMarket,
+    controls: &[Pubkey],
+    mut margin_of: impl FnMut(&Pubkey) -> Option<Pubkey>,
+    max_accounts: usize,
+) -> Vec<Instruction> {
+    let accounts = crate::accounts::CrankPnl {
+        state: *state,
+        state_signer: state_signer_address(state).0,
+        cache: *cache,
+        dex_program: ZO_DEX_PID,
+        market: market.own_address,
+    };
+    let users: Vec<(Pubkey, Pubkey)> = controls
+        .iter()
+        .filter_map(|c| Some((*c, margin_of(c)?)))
+        .collect();
+    let n = chunk_size(max_accounts, 5, 3);
+
+    users
+        .chunks(n)
+        .map(|chunk| {
+            let oo = chunk
+                .iter()
+                .map(|(c, _)| open_orders_address(c, &market.own_address).0);
+
+            let mut metas = accounts.to_account_metas(None);
+            metas.extend(writable(chunk.iter().map(|(c, _)| *c)));
+            metas.extend(writable(oo));
+            metas.extend(writable(chunk.iter().map(|(_, m)| *m)));
+
+            Instruction {
+                program_id: crate::ID,
+                accounts: metas,
+                data: crate::instruction::CrankPnl {}.data(),
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CacheOracleError {
+    /// Longer than a `Symbol` can hold.
+    InvalidSymbol(String),
+    /// Not in `Cache.oracles`.
+    UnknownSymbol(String),
+}
+
+/// `cache_oracle` instruction for `symbols`. Remaining accounts are the
+/// non-nil `OracleSource` keys of each symbol, in the order of `symbols`,
+/// followed by the dex markets whose `oracle_symbol` is one of them.
+pub fn cache_oracle_ix(
+    signer: &Pubkey,
+    state_key: &Pubkey,
+    state: &State,
+    cache: &Cache,
+    symbols: &[String],
+) -> Result<Instruction, CacheOracleError> {
+    let mut parsed: Vec<Symbol> = Vec::with_capacity(symbols.len());
+    let mut metas = crate::accounts::CacheOracle {
+        signer: *signer,
+        state: *state_key,
+        cache: state.cache,
+        dex_program: ZO_DEX_PID,
+    }
+    .to_account_metas(None);
+
+    for s in symbols {
+        let sym = Symbol::try_from(s)
+            .map_err(|_| CacheOracleError::InvalidSymbol(s.clone()))?;
+        let oracle = cache
+            .get_oracle(&sym)
+            .ok_or_else(|| CacheOracleError::UnknownSymbol(s.clone()))?;
+
+        for src in oracle.sources.iter() {
+            if !matches!(src.ty, OracleType::Nil) {
+                metas.push(AccountMeta::new_readonly(src.key, false));
+            }
+        }
+        parsed.push(sym);
+    }
+
+    let markets = state.perp_markets[..state.total_markets as usize]
+        .iter()
+        .filter(|m| parsed.contains(&m.oracle_symbol))
+        .map(|m| m.dex_market);
+    metas.extend(writable(markets));
+
+    Ok(Instruction {
+        program_id: crate::ID,
+        accounts: metas,
+        data: crate::instruction::CacheOracle {
+            symbols: symbols.to_vec(),
+            mock_prices: None,
+        }
+        .data(),
+    })
+}
@@ -1,3 +1,4 @@
+use crate::OpenOrdersInfo;
 use anchor_lang::prelude::Pubkey;
 use bytemuck::{Pod, PodCastError, Zeroable};
 use std::{mem::size_of, num::NonZeroU64};
@@ -27,7 +28,7 @@ pub enum EventFlag {
     ReleaseFunds = 0x10,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum Side {
     Bid = 0,
@@ -190,6 +191,30 @@ impl Event {
         Ok((header, body))
     }
 
+    /// Whether `split`, `deserialize_queue` and `deserialize_since` accept
+    /// `buf` without panicking.
+    pub fn is_valid_queue(buf: &[u8]) -> bool {
+        const FLAGS: u64 = (AccountFlag::Initialized as u64)
+            | (AccountFlag::EventQueue as u64);
+
+        if buf.len() < size_of::<EventQueueHeader>() + 7 {
+            return false;
+        }
+
+        let (header, body) = buf.split_at(size_of::<EventQueueHeader>());
+        let header: &EventQueueHeader = match bytemuck::try_from_bytes(header) {
+            Ok(x) => x,
+            Err(_) => return false,
+        };
+        let len = (body.len() / size_of::<Self>()) as u64;
+
+        header._head_pad[..] == *b"serum"
+            && header.account_flags == FLAGS
+            && body[(body.len() - 7)..] == *b"padding"
+            && header.head < len
+            && header.count <= len
+    }
+
     pub fn deserialize_queue(
         buf: &[u8],
     ) -> Result<
@@ -256,7 +281,7 @@ impl Event {
 #[derive(Copy, Clone, Debug)]
 #[repr(packed)]
 struct InnerNode {
-    _prefix_len: u32,
+    prefix_len: u32,
     _key: u128,
     pub children: [u32; 2],
     _pad: [u8; 40],
@@ -286,6 +311,72 @@ impl LeafNode {
     }
 }
 
+/// `LeafNode.key` with the side of its book. The high 64 bits are the
+/// price in lots and the low 64 bits the sequence number of the order,
+/// inverted for bids so that older bids sort higher at the same price.
+///
+/// Ordered by time priority, oldest first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OrderId {
+    pub key: u128,
+    pub side: Side,
+}
+
+impl OrderId {
+    pub fn new(side: Side, price: u64, seq_num: u64) -> Self {
+        let low = match side {
+            Side::Bid => !seq_num,
+            Side::Ask => seq_num,
+        };
+
+        Self {
+            key: (price as u128) << 64 | low as u128,
+            side,
+        }
+    }
+
+    pub fn from_leaf(n: &LeafNode, side: Side) -> Self {
+        Self { key: n.key, side }
+    }
+
+    /// In price lots.
+    pub fn price(&self) -> u64 {
+        (self.key >> 64) as u64
+    }
+
+    pub fn seq_num(&self) -> u64 {
+        match self.side {
+            Side::Bid => !(self.key as u64),
+            Side::Ask => self.key as u64,
+        }
+    }
+
+    pub fn is_long(&self) -> bool {
+        self.side == Side::Bid
+    }
+
+    /// `order_id` and `is_long` of `cancel_perp_order`.
+    pub fn cancel_args(&self) -> (Option<u128>, Option<bool>) {
+        (Some(self.key), Some(self.is_long()))
+    }
+}
+
+impl Ord for OrderId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.seq_num(), self.side as u8, self.key).cmp(&(
+            other.seq_num(),
+            other.side as u8,
+            other.key,
+        ))
+    }
+}
+
+impl PartialOrd for OrderId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 enum SlabNodeRef<'a> {
     Inner(&'a InnerNode),
     Leaf(&'a LeafNode),
@@ -361,6 +452,65 @@ impl<'a> Slab<'a> {
         })
     }
 
+    /// Whether `deserialize` accepts `buf` without panicking, every node
+    /// has a known tag and the nodes under the root form a tree with
+    /// children in bounds, so that iterating the slab cannot panic or
+    /// loop either.
+    pub fn is_valid(buf: &[u8]) -> bool {
+        if buf.len() < size_of::<SlabHeader>() + 7 {
+            return false;
+        }
+
+        let (head, tail) = buf.split_at(size_of::<SlabHeader>());
+        let head: &SlabHeader = match bytemuck::try_from_bytes(head) {
+            Ok(x) => x,
+            Err(_) => return false,
+        };
+        let tail = &tail[..(tail.len() - tail.len() % size_of::<SlabNode>())];
+        let nodes: &[SlabNode] = match bytemuck::try_cast_slice(tail) {
+            Ok(x) => x,
+            Err(_) => return false,
+        };
+
+        let flags = head.account_flags;
+        if buf[..5] != *b"serum"
+            || buf[(buf.len() - 7)..] != *b"padding"
+            || flags & AccountFlag::Initialized as u64 == 0
+            || (flags & AccountFlag::Bids as u64 != 0)
+                == (flags & AccountFlag::Asks as u64 != 0)
+        {
+            return false;
+        }
+
+        let n = nodes.len() as u32;
+        if head.leaf_count != 0 && head.root >= n {
+            return false;
+        }
+        if nodes.iter().any(|x| x.tag > 4) {
+            return false;
+        }
+        if head.leaf_count == 0 {
+            return true;
+        }
+
+        // Walks the tree, a node reached twice is a cycle or shared.
+        let mut seen = vec![false; nodes.len()];
+        let mut stack = vec![head.root];
+        while let Some(i) = stack.pop() {
+            if std::mem::replace(&mut seen[i as usize], true) {
+                return false;
+            }
+            if let Some(SlabNodeRef::Inner(x)) = nodes[i as usize].load() {
+                let children = x.children;
+                if children.iter().any(|c| *c >= n) {
+                    return false;
+                }
+                stack.extend(children);
+            }
+        }
+        true
+    }
+
     pub fn is_bids(&self) -> bool {
         self.head.account_flags & AccountFlag::Bids as u64 != 0
     }
@@ -418,6 +568,86 @@ impl<'a> Slab<'a> {
             false => self.iter_front(),
         }
     }
+
+    /// Orders of a `Control`, best first.
+    pub fn iter_control(
+        &self,
+        control: &Pubkey,
+    ) -> impl Iterator<Item = &LeafNode> + '_ {
+        let control = *control;
+        self.iter_best().filter(move |x| x.control == control)
+    }
+
+    /// Order by its `key`, the `order_id` of `cancel_perp_order`.
+    pub fn find_order_id(&self, order_id: u128) -> Option<&LeafNode> {
+        if self.head.leaf_count == 0 {
+            return None;
+        }
+
+        // A walk longer than the slab is a cycle in bad data.
+        let mut i = self.head.root;
+        for _ in 0..self.nodes.len() {
+            match self.nodes.get(i as usize)?.load()? {
+                SlabNodeRef::Inner(x) => {
+                    // Bad data, a key has no bit past 127.
+                    if x.prefix_len >= 128 {
+                        return None;
+                    }
+                    let mask = (1u128 << 127) >> x.prefix_len;
+                    i = x.children[(order_id & mask != 0) as usize];
+                }
+                SlabNodeRef::Leaf(x) => {
+                    return (x.key == order_id).then_some(x);
+                }
+            }
+        }
+        None
+    }
+
+    /// Order of a `Control` by its `client_order_id`.
+    pub fn find_client_order_id(
+        &self,
+        control: &Pubkey,
+        client_order_id: u64,
+    ) -> Option<&LeafNode> {
+        self.iter_control(control)
+            .find(|x| x.client_order_id == client_order_id)
+    }
+}
+
+/// Orders of a `Control` on a market's book against its
+/// `OpenOrdersInfo.order_count`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OrderCountCheck {
+    pub on_book: u32,
+    pub order_count: u8,
+}
+
+impl OrderCountCheck {
+    pub fn new(
+        bids: &Slab,
+        asks: &Slab,
+        control: &Pubkey,
+        oo: &OpenOrdersInfo,
+    ) -> Self {
+        let on_book = bids.iter_control(control).count()
+            + asks.iter_control(control).count();
+
+        Self {
+            on_book: on_book as u32,
+            order_count: oo.order_count,
+        }
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.on_book == self.order_count as u32
+    }
+
+    /// Orders counted but no longer on the book, usually fills or
+    /// cancels whose events have not been consumed yet.
+    pub fn ghost_orders(&self) -> u32 {
+        (self.order_count as u32).saturating_sub(self.on_book)
+    }
 }
 
 pub struct SlabIter<'a, 'b: 'a> {
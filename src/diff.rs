@@ -0,0 +1,448 @@
+//! Changes between two versions of the zo accounts, in big units.
+//!
+//! ```ignore
+//! let diff = SnapshotDiff::new(&before, &after);
+//! println!("{}", diff);
+//! // BTC-PERP: position 0.5 -> 0 (-0.5), realized pnl 0 -> 120.5 (+120.5)
+//! ```
+
+use crate::{
+    health::collateral_balance, snapshot::ZoSnapshot, Cache, Control, Margin,
+    SpecialOrderType, SpecialOrders, SpecialOrdersInfo, State, Symbol,
+};
+use fixed::types::I80F48;
+use std::fmt;
+
+/// Change of a `State` field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.before, self.after)
+    }
+}
+
+/// Change of a balance or price.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ValueChange {
+    pub symbol: Symbol,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl ValueChange {
+    pub fn delta(&self) -> f64 {
+        self.after - self.before
+    }
+}
+
+impl fmt::Display for ValueChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {} ({:+})",
+            self.symbol,
+            self.before,
+            self.after,
+            self.delta()
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PositionChange {
+    /// Symbol of the perp market.
+    pub symbol: Symbol,
+    /// In big asset.
+    pub size_before: f64,
+    pub size_after: f64,
+    /// In big quote.
+    pub realized_pnl_before: f64,
+    pub realized_pnl_after: f64,
+}
+
+impl PositionChange {
+    pub fn size_delta(&self) -> f64 {
+        self.size_after - self.size_before
+    }
+
+    pub fn realized_pnl_delta(&self) -> f64 {
+        self.realized_pnl_after - self.realized_pnl_before
+    }
+}
+
+impl fmt::Display for PositionChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: position {} -> {} ({:+}), realized pnl {} -> {} ({:+})",
+            self.symbol,
+            self.size_before,
+            self.size_after,
+            self.size_delta(),
+            self.realized_pnl_before,
+            self.realized_pnl_after,
+            self.realized_pnl_delta()
+        )
+    }
+}
+
+/// Special order in big units.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpecialOrderSummary {
+    pub id: u16,
+    /// Symbol of the perp market, `None` if `State` does not list the
+    /// order's dex market.
+    pub symbol: Option<Symbol>,
+    pub ty: SpecialOrderType,
+    pub is_long: bool,
+    /// In big quote per big asset.
+    pub trigger_price: f64,
+    pub limit_price: f64,
+    /// In big asset, or in lots if the market is not listed.
+    pub size: f64,
+}
+
+impl SpecialOrderSummary {
+    pub fn new(state: &State, x: &SpecialOrdersInfo) -> Self {
+        let market = state.perp_markets[..state.total_markets as usize]
+            .iter()
+            .find(|m| m.dex_market == x.market);
+        let q = 10f64.powi(quote_decimals(state));
+        let lot = match market {
+            Some(m) => {
+                m.asset_lot_size as f64 / 10f64.powi(m.asset_decimals as i32)
+            }
+            None => 1.0,
+        };
+
+        Self {
+            id: x.id,
+            symbol: market.map(|m| m.symbol),
+            ty: x.ty,
+            is_long: x.is_long,
+            trigger_price: x.trigger_price as f64 / q,
+            limit_price: x.limit_price as f64 / q,
+            size: x.size as f64 * lot,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpecialOrderChange {
+    Added(SpecialOrderSummary),
+    Removed(SpecialOrderSummary),
+}
+
+impl fmt::Display for SpecialOrderChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (verb, x) = match self {
+            Self::Added(x) => ("added", x),
+            Self::Removed(x) => ("removed", x),
+        };
+
+        write!(
+            f,
+            "special order {} {}: {:?} {} ",
+            x.id,
+            verb,
+            x.ty,
+            if x.is_long { "long" } else { "short" },
+        )?;
+        match x.symbol {
+            Some(s) => write!(f, "{} {}", x.size, s)?,
+            None => write!(f, "{} lots of an unlisted market", x.size)?,
+        }
+        write!(f, " at {}", x.trigger_price)?;
+        if x.limit_price != 0.0 {
+            write!(f, ", limit {}", x.limit_price)?;
+        }
+        Ok(())
+    }
+}
+
+fn quote_decimals(state: &State) -> i32 {
+    state.collaterals[0].decimals as i32
+}
+
+/// Changes of `State.insurance`, of the collaterals and perp markets
+/// listed, and of their settings.
+pub fn diff_state(before: &State, after: &State) -> Vec<FieldChange> {
+    let mut r: Vec<FieldChange> = Vec::new();
+    let mut push = |field: String, a: String, b: String| {
+        if a != b {
+            r.push(FieldChange {
+                field,
+                before: a,
+                after: b,
+            });
+        }
+    };
+
+    let q = 10f64.powi(quote_decimals(after));
+    let (a, b) = (before.insurance, after.insurance);
+    push(
+        "insurance".into(),
+        (a as f64 / q).to_string(),
+        (b as f64 / q).to_string(),
+    );
+    let (a, b) = (before.total_collaterals, after.total_collaterals);
+    push("total_collaterals".into(), a.to_string(), b.to_string());
+    let (a, b) = (before.total_markets, after.total_markets);
+    push("total_markets".into(), a.to_string(), b.to_string());
+
+    let n = before.total_collaterals.max(after.total_collaterals) as usize;
+    for i in 0..n {
+        let (a, b) = (&before.collaterals[i], &after.collaterals[i]);
+        let name = |s: &str| format!("collaterals[{}].{}", b.oracle_symbol, s);
+        let (wa, wb) = (a.weight, b.weight);
+        push(name("weight"), wa.to_string(), wb.to_string());
+        let (la, lb) = (a.liq_fee, b.liq_fee);
+        push(name("liq_fee"), la.to_string(), lb.to_string());
+        let (ma, mb) = (a.max_deposit, b.max_deposit);
+        push(name("max_deposit"), ma.to_string(), mb.to_string());
+    }
+
+    let n = before.total_markets.max(after.total_markets) as usize;
+    for i in 0..n {
+        let (a, b) = (&before.perp_markets[i], &after.perp_markets[i]);
+        let name = |s: &str| format!("perp_markets[{}].{}", b.symbol, s);
+        let (ia, ib) = (a.base_imf, b.base_imf);
+        push(name("base_imf"), ia.to_string(), ib.to_string());
+        let (la, lb) = (a.liq_fee, b.liq_fee);
+        push(name("liq_fee"), la.to_string(), lb.to_string());
+        let (sa, sb) = (a.strike, b.strike);
+        push(name("strike"), sa.to_string(), sb.to_string());
+    }
+
+    r
+}
+
+/// Oracle price changes, in big quote per big asset.
+pub fn diff_oracles(before: &Cache, after: &Cache) -> Vec<ValueChange> {
+    let price = |c: &Cache, s: &Symbol| {
+        let o = c.get_oracle(s)?;
+        let adj = 10f64.powi(o.base_decimals as i32 - o.quote_decimals as i32);
+        Some(I80F48::from(o.price).to_num::<f64>() * adj)
+    };
+
+    after
+        .oracles
+        .iter()
+        .filter(|o| !o.symbol.is_nil())
+        .filter_map(|o| {
+            let before = price(before, &o.symbol)?;
+            let after = price(after, &o.symbol)?;
+            (before != after).then_some(ValueChange {
+                symbol: o.symbol,
+                before,
+                after,
+            })
+        })
+        .collect()
+}
+
+/// Mark price changes, in big quote per big asset.
+pub fn diff_marks(
+    state: &State,
+    before: &Cache,
+    after: &Cache,
+) -> Vec<ValueChange> {
+    let q = quote_decimals(state);
+
+    state.perp_markets[..state.total_markets as usize]
+        .iter()
+        .enumerate()
+        .filter_map(|(i, m)| {
+            let adj = 10f64.powi(m.asset_decimals as i32 - q);
+            let before = I80F48::from(before.marks[i].price).to_num::<f64>();
+            let after = I80F48::from(after.marks[i].price).to_num::<f64>();
+            (before != after).then_some(ValueChange {
+                symbol: m.symbol,
+                before: before * adj,
+                after: after * adj,
+            })
+        })
+        .collect()
+}
+
+/// Collateral balance changes with interest, in big units, keyed by
+/// `CollateralInfo.oracle_symbol`.
+pub fn diff_collaterals(
+    state: &State,
+    before: (&Cache, &Margin),
+    after: (&Cache, &Margin),
+) -> Vec<ValueChange> {
+    (0..state.total_collaterals as usize)
+        .filter_map(|i| {
+            let info = &state.collaterals[i];
+            let d = 10f64.powi(info.decimals as i32);
+            let a = collateral_balance(before.0, before.1, i).to_num::<f64>();
+            let b = collateral_balance(after.0, after.1, i).to_num::<f64>();
+            (a != b).then_some(ValueChange {
+                symbol: info.oracle_symbol,
+                before: a / d,
+                after: b / d,
+            })
+        })
+        .collect()
+}
+
+/// Changes of position size or realized pnl.
+pub fn diff_positions(
+    state: &State,
+    before: &Control,
+    after: &Control,
+) -> Vec<PositionChange> {
+    let q = 10f64.powi(quote_decimals(state));
+
+    state.perp_markets[..state.total_markets as usize]
+        .iter()
+        .enumerate()
+        .filter_map(|(i, m)| {
+            let (a, b) =
+                (&before.open_orders_agg[i], &after.open_orders_agg[i]);
+            let (sa, sb) = (a.pos_size, b.pos_size);
+            let (pa, pb) = (a.realized_pnl, b.realized_pnl);
+            if sa == sb && pa == pb {
+                return None;
+            }
+
+            let d = 10f64.powi(m.asset_decimals as i32);
+            Some(PositionChange {
+                symbol: m.symbol,
+                size_before: sa as f64 / d,
+                size_after: sb as f64 / d,
+                realized_pnl_before: pa as f64 / q,
+                realized_pnl_after: pb as f64 / q,
+            })
+        })
+        .collect()
+}
+
+/// Special orders removed, then added, by `id`.
+pub fn diff_special_orders(
+    state: &State,
+    before: &SpecialOrders,
+    after: &SpecialOrders,
+) -> Vec<SpecialOrderChange> {
+    let key = |x: &SpecialOrdersInfo| {
+        let SpecialOrdersInfo {
+            id,
+            market,
+            ty,
+            is_long,
+            trigger_price,
+            limit_price,
+            size,
+            fee,
+        } = *x;
+        (
+            id,
+            market,
+            ty,
+            is_long,
+            trigger_price,
+            limit_price,
+            size,
+            fee,
+        )
+    };
+    let same = |a: &SpecialOrdersInfo, b: &SpecialOrdersInfo| key(a) == key(b);
+
+    let removed = before
+        .iter()
+        .filter(|a| !after.iter().any(|b| same(a, b)))
+        .map(|x| {
+            SpecialOrderChange::Removed(SpecialOrderSummary::new(state, x))
+        });
+    let added = after
+        .iter()
+        .filter(|b| !before.iter().any(|a| same(a, b)))
+        .map(|x| SpecialOrderChange::Added(SpecialOrderSummary::new(state, x)));
+
+    removed.chain(added).collect()
+}
+
+/// Changes between two snapshots of the same margin. Units follow the
+/// later `State`.
+#[derive(Clone)]
+pub struct SnapshotDiff {
+    pub state: Vec<FieldChange>,
+    pub oracles: Vec<ValueChange>,
+    pub marks: Vec<ValueChange>,
+    pub collaterals: Vec<ValueChange>,
+    pub positions: Vec<PositionChange>,
+    pub special_orders: Vec<SpecialOrderChange>,
+}
+
+impl SnapshotDiff {
+    pub fn new(before: &ZoSnapshot, after: &ZoSnapshot) -> Self {
+        let state = after.state();
+        let summary = |x| SpecialOrderSummary::new(state, x);
+        let special_orders =
+            match (before.special_orders(), after.special_orders()) {
+                (Some(a), Some(b)) => diff_special_orders(state, a, b),
+                (None, Some(b)) => b
+                    .iter()
+                    .map(|x| SpecialOrderChange::Added(summary(x)))
+                    .collect(),
+                (Some(a), None) => a
+                    .iter()
+                    .map(|x| SpecialOrderChange::Removed(summary(x)))
+                    .collect(),
+                (None, None) => Vec::new(),
+            };
+
+        Self {
+            state: diff_state(before.state(), state),
+            oracles: diff_oracles(before.cache(), after.cache()),
+            marks: diff_marks(state, before.cache(), after.cache()),
+            collaterals: diff_collaterals(
+                state,
+                (before.cache(), before.margin()),
+                (after.cache(), after.margin()),
+            ),
+            positions: diff_positions(state, before.control(), after.control()),
+            special_orders,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.is_empty()
+            && self.oracles.is_empty()
+            && self.marks.is_empty()
+            && self.collaterals.is_empty()
+            && self.positions.is_empty()
+            && self.special_orders.is_empty()
+    }
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for x in self.state.iter() {
+            writeln!(f, "{}", x)?;
+        }
+        for x in self.oracles.iter() {
+            writeln!(f, "oracle {}", x)?;
+        }
+        for x in self.marks.iter() {
+            writeln!(f, "mark {}", x)?;
+        }
+        for x in self.collaterals.iter() {
+            writeln!(f, "balance {}", x)?;
+        }
+        for x in self.positions.iter() {
+            writeln!(f, "{}", x)?;
+        }
+        for x in self.special_orders.iter() {
+            writeln!(f, "{}", x)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,671 @@
+//! Decodes the errors of failed zo transactions.
+//!
+//! Errors raised by anchor itself, such as account constraint violations,
+//! are mapped to `anchor_lang::error::ErrorCode`. The zo program's own
+//! codes, from `ERROR_CODE_OFFSET`, are mapped to `ZoErrorCode`.
+//!
+//! The zo dex is a fork of the serum dex and raises the same codes, which
+//! are mapped to `DexError`.
+//!
+//! ```ignore
+//! if let Err(e) = simulate(tx) {
+//!     if let Some(f) = ProgramFailure::from_logs(&e.logs) {
+//!         println!("{}", f); // zo: ConstraintSeeds (2006): ...
+//!         // or: zo: NotLiquidatable (6020): ...
+//!         // or: zo dex: OrderNotFound (59): ...
+//!     }
+//! }
+//! ```
+
+use crate::{SERUM_DEX_PID, ZO_DEX_PID};
+use anchor_lang::{
+    error::{ErrorCode, ERROR_CODE_OFFSET},
+    prelude::Pubkey,
+};
+use solana_program::instruction::InstructionError;
+use std::{fmt, str::FromStr};
+
+/// Program that raised an error.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorProgram {
+    Zo,
+    ZoDex,
+    SerumDex,
+    Other(Pubkey),
+}
+
+impl ErrorProgram {
+    pub fn from_id(id: &Pubkey) -> Self {
+        match *id {
+            x if x == crate::ID => Self::Zo,
+            x if x == ZO_DEX_PID => Self::ZoDex,
+            x if x == SERUM_DEX_PID => Self::SerumDex,
+            x => Self::Other(x),
+        }
+    }
+}
+
+impl fmt::Display for ErrorProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Zo => write!(f, "zo"),
+            Self::ZoDex => write!(f, "zo dex"),
+            Self::SerumDex => write!(f, "serum dex"),
+            Self::Other(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+/// Error code of the zo program, from `ERROR_CODE_OFFSET`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ZoErrorCode {
+    MathFailure = 6000,
+    Unauthorized,
+    InsufficientCollateral,
+    CollateralAlreadyExists,
+    CollateralDoesNotExist,
+    CollateralLimitReached,
+    OracleAlreadyExists,
+    OracleDoesNotExist,
+    OracleLimitReached,
+    InvalidOracle,
+    StaleOracle,
+    MarketAlreadyExists,
+    MarketDoesNotExist,
+    MarketLimitReached,
+    InvalidDexMarket,
+    InvalidOpenOrders,
+    InvalidVault,
+    InsufficientSupply,
+    NotSwappable,
+    SlippageExceeded,
+    NotLiquidatable,
+    SelfLiquidation,
+    LiquidationOverExposure,
+    NotBankrupt,
+    Bankrupt,
+    RemainingAccountsMissing,
+    StaleCache,
+    SpecialOrdersFull,
+    SpecialOrderNotFound,
+    InvalidSpecialOrder,
+}
+
+impl ZoErrorCode {
+    pub fn from_code(code: u32) -> Option<Self> {
+        use ZoErrorCode::*;
+
+        const CODES: [ZoErrorCode; 30] = [
+            MathFailure,
+            Unauthorized,
+            InsufficientCollateral,
+            CollateralAlreadyExists,
+            CollateralDoesNotExist,
+            CollateralLimitReached,
+            OracleAlreadyExists,
+            OracleDoesNotExist,
+            OracleLimitReached,
+            InvalidOracle,
+            StaleOracle,
+            MarketAlreadyExists,
+            MarketDoesNotExist,
+            MarketLimitReached,
+            InvalidDexMarket,
+            InvalidOpenOrders,
+            InvalidVault,
+            InsufficientSupply,
+            NotSwappable,
+            SlippageExceeded,
+            NotLiquidatable,
+            SelfLiquidation,
+            LiquidationOverExposure,
+            NotBankrupt,
+            Bankrupt,
+            RemainingAccountsMissing,
+            StaleCache,
+            SpecialOrdersFull,
+            SpecialOrderNotFound,
+            InvalidSpecialOrder,
+        ];
+
+        let i = code.checked_sub(ERROR_CODE_OFFSET)?;
+        CODES.get(i as usize).copied()
+    }
+
+    pub fn message(&self) -> &'static str {
+        use ZoErrorCode::*;
+
+        match self {
+            MathFailure => "Math operation failed",
+            Unauthorized => "Signer is not authorized",
+            InsufficientCollateral => "Insufficient collateral",
+            CollateralAlreadyExists => "Collateral already exists",
+            CollateralDoesNotExist => "Collateral does not exist",
+            CollateralLimitReached => "Maximum number of collaterals reached",
+            OracleAlreadyExists => "Oracle already exists",
+            OracleDoesNotExist => "Oracle does not exist",
+            OracleLimitReached => "Maximum number of oracles reached",
+            InvalidOracle => "Oracle account does not match the cache",
+            StaleOracle => "Oracle price is stale",
+            MarketAlreadyExists => "Market already exists",
+            MarketDoesNotExist => "Market does not exist",
+            MarketLimitReached => "Maximum number of markets reached",
+            InvalidDexMarket => "Dex market does not match the state",
+            InvalidOpenOrders => {
+                "Open orders account does not match the control"
+            }
+            InvalidVault => "Vault does not match the collateral",
+            InsufficientSupply => "Not enough supply to borrow",
+            NotSwappable => "Collateral is not swappable",
+            SlippageExceeded => "Swap rate is below the minimum",
+            NotLiquidatable => "Margin is not below maintenance",
+            SelfLiquidation => "Cannot liquidate own margin",
+            LiquidationOverExposure => "Liquidation exceeds the position",
+            NotBankrupt => "Margin is not bankrupt",
+            Bankrupt => "Margin is bankrupt",
+            RemainingAccountsMissing => "Missing remaining accounts",
+            StaleCache => "Cache is stale",
+            SpecialOrdersFull => "No free special order slot",
+            SpecialOrderNotFound => "Special order not found",
+            InvalidSpecialOrder => "Special order is invalid",
+        }
+    }
+}
+
+impl fmt::Display for ZoErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({}): {}", self, *self as u32, self.message())
+    }
+}
+
+/// Error code of the zo program.
+#[derive(Copy, Clone, Debug)]
+pub enum ZoError {
+    /// Raised by anchor, below `ERROR_CODE_OFFSET`.
+    Anchor(ErrorCode),
+    /// Raised by the zo program.
+    Program(ZoErrorCode),
+    /// A code neither anchor nor the zo program define.
+    Custom(u32),
+}
+
+impl ZoError {
+    pub fn from_code(code: u32) -> Self {
+        if let Some(e) = anchor_error_code(code) {
+            return Self::Anchor(e);
+        }
+        match ZoErrorCode::from_code(code) {
+            Some(e) => Self::Program(e),
+            None => Self::Custom(code),
+        }
+    }
+
+    pub fn code(&self) -> u32 {
+        match *self {
+            Self::Anchor(e) => e.into(),
+            Self::Program(e) => e as u32,
+            Self::Custom(x) => x,
+        }
+    }
+}
+
+impl PartialEq for ZoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl Eq for ZoError {}
+
+impl fmt::Display for ZoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Anchor(e) => {
+                write!(f, "{} ({}): {}", e.name(), self.code(), e)
+            }
+            Self::Program(e) => write!(f, "{}", e),
+            Self::Custom(x) => write!(f, "unknown error {} ({:#x})", x, x),
+        }
+    }
+}
+
+/// Error code of the serum dex and zo dex, from `DexErrorCode` of
+/// serum-dex.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum DexErrorCode {
+    InvalidMarketFlags = 0,
+    InvalidAskFlags,
+    InvalidBidFlags,
+    InvalidQueueLength,
+    OwnerAccountNotProvided,
+
+    ConsumeEventsQueueFailure,
+    WrongCoinVault,
+    WrongPcVault,
+    WrongCoinMint,
+    WrongPcMint,
+
+    CoinVaultProgramId = 10,
+    PcVaultProgramId,
+    CoinMintProgramId,
+    PcMintProgramId,
+
+    WrongCoinMintSize,
+    WrongPcMintSize,
+    WrongCoinVaultSize,
+    WrongPcVaultSize,
+
+    UninitializedVault,
+    UninitializedMint,
+
+    CoinMintUninitialized = 20,
+    PcMintUninitialized,
+    WrongMint,
+    WrongVaultOwner,
+    VaultHasDelegate,
+
+    AlreadyInitialized,
+    WrongAccountDataAlignment,
+    WrongAccountDataPaddingLength,
+    WrongAccountHeadPadding,
+    WrongAccountTailPadding,
+
+    RequestQueueEmpty = 30,
+    EventQueueTooSmall,
+    SlabTooSmall,
+    BadVaultSignerNonce,
+    InsufficientFunds,
+
+    SplAccountProgramId,
+    SplAccountLen,
+    WrongFeeDiscountAccountOwner,
+    WrongFeeDiscountMint,
+
+    CoinPayerProgramId,
+    PcPayerProgramId = 40,
+    ClientIdNotFound,
+    TooManyOpenOrders,
+
+    FakeErrorSoWeDontChangeNumbers,
+    BorrowError,
+
+    WrongOrdersAccount,
+    WrongBidsAccount,
+    WrongAsksAccount,
+    WrongRequestQueueAccount,
+    WrongEventQueueAccount,
+
+    RequestQueueFull = 50,
+    EventQueueFull,
+    MarketIsDisabled,
+    WrongSigner,
+    TransferFailed,
+    ClientOrderIdIsZero,
+
+    WrongRentSysvarAccount,
+    RentNotProvided,
+    OrdersNotRentExempt,
+    OrderNotFound,
+    OrderNotYours,
+
+    WouldSelfTrade,
+
+    InvalidOpenOrdersAuthority,
+
+    Unknown = 1000,
+}
+
+impl DexErrorCode {
+    pub fn from_code(code: u32) -> Option<Self> {
+        use DexErrorCode::*;
+
+        const CODES: [DexErrorCode; 63] = [
+            InvalidMarketFlags,
+            InvalidAskFlags,
+            InvalidBidFlags,
+            InvalidQueueLength,
+            OwnerAccountNotProvided,
+            ConsumeEventsQueueFailure,
+            WrongCoinVault,
+            WrongPcVault,
+            WrongCoinMint,
+            WrongPcMint,
+            CoinVaultProgramId,
+            PcVaultProgramId,
+            CoinMintProgramId,
+            PcMintProgramId,
+            WrongCoinMintSize,
+            WrongPcMintSize,
+            WrongCoinVaultSize,
+            WrongPcVaultSize,
+            UninitializedVault,
+            UninitializedMint,
+            CoinMintUninitialized,
+            PcMintUninitialized,
+            WrongMint,
+            WrongVaultOwner,
+            VaultHasDelegate,
+            AlreadyInitialized,
+            WrongAccountDataAlignment,
+            WrongAccountDataPaddingLength,
+            WrongAccountHeadPadding,
+            WrongAccountTailPadding,
+            RequestQueueEmpty,
+            EventQueueTooSmall,
+            SlabTooSmall,
+            BadVaultSignerNonce,
+            InsufficientFunds,
+            SplAccountProgramId,
+            SplAccountLen,
+            WrongFeeDiscountAccountOwner,
+            WrongFeeDiscountMint,
+            CoinPayerProgramId,
+            PcPayerProgramId,
+            ClientIdNotFound,
+            TooManyOpenOrders,
+            FakeErrorSoWeDontChangeNumbers,
+            BorrowError,
+            WrongOrdersAccount,
+            WrongBidsAccount,
+            WrongAsksAccount,
+            WrongRequestQueueAccount,
+            WrongEventQueueAccount,
+            RequestQueueFull,
+            EventQueueFull,
+            MarketIsDisabled,
+            WrongSigner,
+            TransferFailed,
+            ClientOrderIdIsZero,
+            WrongRentSysvarAccount,
+            RentNotProvided,
+            OrdersNotRentExempt,
+            OrderNotFound,
+            OrderNotYours,
+            WouldSelfTrade,
+            InvalidOpenOrdersAuthority,
+        ];
+
+        match code {
+            1000 => Some(Unknown),
+            x => CODES.get(x as usize).copied(),
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        use DexErrorCode::*;
+
+        match self {
+            InvalidMarketFlags => "Market account flags are invalid",
+            InvalidAskFlags => "Asks account flags are invalid",
+            InvalidBidFlags => "Bids account flags are invalid",
+            InvalidQueueLength => "Queue length is invalid",
+            OwnerAccountNotProvided => "Owner account was not provided",
+            ConsumeEventsQueueFailure => "Failed to consume events",
+            WrongCoinVault => "Wrong coin vault",
+            WrongPcVault => "Wrong pc vault",
+            WrongCoinMint => "Wrong coin mint",
+            WrongPcMint => "Wrong pc mint",
+            CoinVaultProgramId => "Coin vault is not a token account",
+            PcVaultProgramId => "Pc vault is not a token account",
+            CoinMintProgramId => "Coin mint is not a token mint",
+            PcMintProgramId => "Pc mint is not a token mint",
+            WrongCoinMintSize => "Coin mint has the wrong size",
+            WrongPcMintSize => "Pc mint has the wrong size",
+            WrongCoinVaultSize => "Coin vault has the wrong size",
+            WrongPcVaultSize => "Pc vault has the wrong size",
+            UninitializedVault => "Vault is not initialized",
+            UninitializedMint => "Mint is not initialized",
+            CoinMintUninitialized => "Coin mint is not initialized",
+            PcMintUninitialized => "Pc mint is not initialized",
+            WrongMint => "Wrong mint",
+            WrongVaultOwner => "Vault is not owned by the vault signer",
+            VaultHasDelegate => "Vault has a delegate",
+            AlreadyInitialized => "Account is already initialized",
+            WrongAccountDataAlignment => "Account data is misaligned",
+            WrongAccountDataPaddingLength => {
+                "Account data padding has the wrong length"
+            }
+            WrongAccountHeadPadding => "Account head padding is wrong",
+            WrongAccountTailPadding => "Account tail padding is wrong",
+            RequestQueueEmpty => "Request queue is empty",
+            EventQueueTooSmall => "Event queue is too small",
+            SlabTooSmall => "Order book is too small",
+            BadVaultSignerNonce => "Vault signer nonce is wrong",
+            InsufficientFunds => "Insufficient funds",
+            SplAccountProgramId => "Account is not a token account",
+            SplAccountLen => "Token account has the wrong size",
+            WrongFeeDiscountAccountOwner => {
+                "Fee discount account has the wrong owner"
+            }
+            WrongFeeDiscountMint => "Fee discount account has the wrong mint",
+            CoinPayerProgramId => "Coin payer is not a token account",
+            PcPayerProgramId => "Pc payer is not a token account",
+            ClientIdNotFound => "Client order id not found",
+            TooManyOpenOrders => "Too many open orders",
+            FakeErrorSoWeDontChangeNumbers => "Unused",
+            BorrowError => "Account is already borrowed",
+            WrongOrdersAccount => "Wrong open orders account",
+            WrongBidsAccount => "Wrong bids account",
+            WrongAsksAccount => "Wrong asks account",
+            WrongRequestQueueAccount => "Wrong request queue account",
+            WrongEventQueueAccount => "Wrong event queue account",
+            RequestQueueFull => "Request queue is full",
+            EventQueueFull => "Event queue is full",
+            MarketIsDisabled => "Market is disabled",
+            WrongSigner => "Wrong signer",
+            TransferFailed => "Token transfer failed",
+            ClientOrderIdIsZero => "Client order id is zero",
+            WrongRentSysvarAccount => "Wrong rent sysvar account",
+            RentNotProvided => "Rent sysvar was not provided",
+            OrdersNotRentExempt => "Open orders account is not rent exempt",
+            OrderNotFound => "Order not found",
+            OrderNotYours => "Order belongs to another open orders account",
+            WouldSelfTrade => {
+                "Order would trade with an order of the same account"
+            }
+            InvalidOpenOrdersAuthority => "Wrong open orders authority",
+            Unknown => "Unknown error",
+        }
+    }
+}
+
+impl fmt::Display for DexErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({}): {}", self, *self as u32, self.message())
+    }
+}
+
+/// Error of the serum dex or zo dex.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DexError {
+    Code(DexErrorCode),
+    /// A failed `check_assert`, encoded as the line in the low 24 bits
+    /// and the id of the source file in the high 8.
+    Assertion {
+        file_id: u8,
+        line: u32,
+    },
+    Unknown(u32),
+}
+
+impl DexError {
+    pub fn from_code(code: u32) -> Self {
+        match code >> 24 {
+            0 => match DexErrorCode::from_code(code) {
+                Some(e) => Self::Code(e),
+                None => Self::Unknown(code),
+            },
+            file_id => Self::Assertion {
+                file_id: file_id as u8,
+                line: code & 0xff_ffff,
+            },
+        }
+    }
+}
+
+impl fmt::Display for DexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Code(e) => write!(f, "{}", e),
+            Self::Assertion { file_id, line } => {
+                write!(f, "assertion failed in file {} line {}", file_id, line)
+            }
+            Self::Unknown(x) => write!(f, "unknown error {} ({:#x})", x, x),
+        }
+    }
+}
+
+/// Custom program error and the program that raised it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ProgramFailure {
+    pub program_id: Pubkey,
+    pub code: u32,
+}
+
+impl ProgramFailure {
+    /// Finds the innermost program that failed with a custom error. When
+    /// a CPI fails, the callee logs its failure before the caller does,
+    /// so this is the first such line.
+    pub fn from_logs<S: AsRef<str>>(logs: &[S]) -> Option<Self> {
+        logs.iter().find_map(|x| parse_failed_log(x.as_ref()))
+    }
+
+    /// From the error of a failed instruction, as in
+    /// `TransactionError::InstructionError(index, err)`. `program_id` is
+    /// the instruction's program, used if `logs` do not say which program
+    /// raised the error, for example when a CPI failed.
+    pub fn from_instruction_error<S: AsRef<str>>(
+        program_id: &Pubkey,
+        err: &InstructionError,
+        logs: &[S],
+    ) -> Option<Self> {
+        let code = match err {
+            InstructionError::Custom(x) => *x,
+            _ => return None,
+        };
+
+        match Self::from_logs(logs) {
+            Some(x) if x.code == code => Some(x),
+            _ => Some(Self {
+                program_id: *program_id,
+                code,
+            }),
+        }
+    }
+
+    pub fn program(&self) -> ErrorProgram {
+        ErrorProgram::from_id(&self.program_id)
+    }
+
+    /// The zo error, if the zo program raised it.
+    pub fn zo_error(&self) -> Option<ZoError> {
+        match self.program() {
+            ErrorProgram::Zo => Some(ZoError::from_code(self.code)),
+            _ => None,
+        }
+    }
+
+    /// The dex error, if the zo dex or serum dex raised it.
+    pub fn dex_error(&self) -> Option<DexError> {
+        match self.program() {
+            ErrorProgram::ZoDex | ErrorProgram::SerumDex => {
+                Some(DexError::from_code(self.code))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ProgramFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(e) = self.zo_error() {
+            return write!(f, "{}: {}", self.program(), e);
+        }
+        if let Some(e) = self.dex_error() {
+            return write!(f, "{}: {}", self.program(), e);
+        }
+        write!(
+            f,
+            "{}: custom program error {:#x}",
+            self.program(),
+            self.code
+        )
+    }
+}
+
+/// Parses `Program <id> failed: custom program error: 0x<code>`.
+fn parse_failed_log(line: &str) -> Option<ProgramFailure> {
+    let rest = line.strip_prefix("Program ")?;
+    let (id, rest) = rest.split_once(' ')?;
+    let code = rest.strip_prefix("failed: custom program error: 0x")?;
+
+    Some(ProgramFailure {
+        program_id: Pubkey::from_str(id).ok()?,
+        code: u32::from_str_radix(code.trim(), 16).ok()?,
+    })
+}
+
+/// Anchor framework error for `code`, if any.
+pub fn anchor_error_code(code: u32) -> Option<ErrorCode> {
+    use ErrorCode::*;
+
+    Some(match code {
+        100 => InstructionMissing,
+        101 => InstructionFallbackNotFound,
+        102 => InstructionDidNotDeserialize,
+        103 => InstructionDidNotSerialize,
+        1000 => IdlInstructionStub,
+        1001 => IdlInstructionInvalidProgram,
+        2000 => ConstraintMut,
+        2001 => ConstraintHasOne,
+        2002 => ConstraintSigner,
+        2003 => ConstraintRaw,
+        2004 => ConstraintOwner,
+        2005 => ConstraintRentExempt,
+        2006 => ConstraintSeeds,
+        2007 => ConstraintExecutable,
+        2008 => ConstraintState,
+        2009 => ConstraintAssociated,
+        2010 => ConstraintAssociatedInit,
+        2011 => ConstraintClose,
+        2012 => ConstraintAddress,
+        2013 => ConstraintZero,
+        2014 => ConstraintTokenMint,
+        2015 => ConstraintTokenOwner,
+        2016 => ConstraintMintMintAuthority,
+        2017 => ConstraintMintFreezeAuthority,
+        2018 => ConstraintMintDecimals,
+        2019 => ConstraintSpace,
+        2500 => RequireViolated,
+        2501 => RequireEqViolated,
+        2502 => RequireKeysEqViolated,
+        2503 => RequireNeqViolated,
+        2504 => RequireKeysNeqViolated,
+        2505 => RequireGtViolated,
+        2506 => RequireGteViolated,
+        3000 => AccountDiscriminatorAlreadySet,
+        3001 => AccountDiscriminatorNotFound,
+        3002 => AccountDiscriminatorMismatch,
+        3003 => AccountDidNotDeserialize,
+        3004 => AccountDidNotSerialize,
+        3005 => AccountNotEnoughKeys,
+        3006 => AccountNotMutable,
+        3007 => AccountOwnedByWrongProgram,
+        3008 => InvalidProgramId,
+        3009 => InvalidProgramExecutable,
+        3010 => AccountNotSigner,
+        3011 => AccountNotSystemOwned,
+        3012 => AccountNotInitialized,
+        3013 => AccountNotProgramData,
+        3014 => AccountNotAssociatedTokenAccount,
+        3015 => AccountSysvarMismatch,
+        3016 => AccountReallocExceedsLimit,
+        3017 => AccountDuplicateReallocs,
+        4000 => StateInvalidAddress,
+        4100 => DeclaredProgramIdMismatch,
+        5000 => Deprecated,
+        _ => return None,
+    })
+}
@@ -0,0 +1,94 @@
+//! Fee tiers and fees of perp fills.
+//!
+//! Rates are those of `taker_rate` and `maker_rate`. Taker fees round up
+//! and maker rebates round down, in smol quote.
+
+use crate::{events::EventFillLog, maker_rate, taker_rate, FeeTier, PerpType};
+
+/// Decimals of the ZO token.
+pub const ZO_DECIMALS: u8 = 6;
+
+/// Minimum big ZO held for each ZO tier, lowest first.
+pub const ZO_FEE_TIERS: [(FeeTier, u64); 5] = [
+    (FeeTier::Zo2, 100),
+    (FeeTier::Zo3, 1_000),
+    (FeeTier::Zo4, 10_000),
+    (FeeTier::Zo5, 100_000),
+    (FeeTier::Zo6, 1_000_000),
+];
+
+/// Fee tier of a trader holding `zo` smol ZO and `msrm` MSRM. Holding
+/// any MSRM gives the `Msrm` tier regardless of ZO.
+pub fn fee_tier(zo: u64, msrm: u64) -> FeeTier {
+    if msrm > 0 {
+        return FeeTier::Msrm;
+    }
+
+    let big = zo / 10u64.pow(ZO_DECIMALS as u32);
+    ZO_FEE_TIERS
+        .iter()
+        .rev()
+        .find(|(_, min)| big >= *min)
+        .map_or(FeeTier::Base, |(tier, _)| *tier)
+}
+
+/// Fee paid by a taker, or rebate received by a maker, on a fill of
+/// `notional` smol quote.
+pub fn fill_fee(
+    perp_type: PerpType,
+    tier: FeeTier,
+    is_maker: bool,
+    notional: u64,
+) -> u64 {
+    let (rate, round_up) = match is_maker {
+        true => (maker_rate(perp_type, tier), false),
+        false => (taker_rate(perp_type, tier), true),
+    };
+    let n = notional as u128 * rate as u128;
+
+    match round_up {
+        true => ((n + 99_999) / 100_000) as u64,
+        false => (n / 100_000) as u64,
+    }
+}
+
+/// Notional of a logged fill in smol quote, before fees. As on serum,
+/// a taker's `qty_paid` includes the fee and `qty_received` is net of
+/// it, while a maker's rebate reduces what it pays or adds to what it
+/// receives.
+pub fn fill_notional(log: &EventFillLog) -> u64 {
+    let fee = log.fee_or_rebate;
+
+    match (log.is_long, log.is_maker) {
+        (true, false) => log.qty_paid.saturating_sub(fee),
+        (false, false) => log.qty_received + fee,
+        (true, true) => log.qty_paid + fee,
+        (false, true) => log.qty_received.saturating_sub(fee),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FillFeeCheck {
+    pub notional: u64,
+    /// `fill_fee` at the tier.
+    pub expected: u64,
+    /// `EventFillLog.fee_or_rebate`.
+    pub actual: u64,
+}
+
+impl FillFeeCheck {
+    pub fn new(log: &EventFillLog, perp_type: PerpType, tier: FeeTier) -> Self {
+        let notional = fill_notional(log);
+
+        Self {
+            notional,
+            expected: fill_fee(perp_type, tier, log.is_maker, notional),
+            actual: log.fee_or_rebate,
+        }
+    }
+
+    /// Whether the logged fee matches, up to one smol of rounding.
+    pub fn is_consistent(&self) -> bool {
+        self.expected.abs_diff(self.actual) <= 1
+    }
+}
@@ -0,0 +1,87 @@
+//! Estimates the next funding payment of a perp market.
+//!
+//! Funding is paid hourly by `update_perp_funding` at a daily rate of the
+//! premium of the mark TWAP over the oracle TWAP, so each payment is a
+//! 24th of the premium. Positive funding is paid by longs to shorts.
+
+use crate::{dex::ZoDexMarket, Cache, OpenOrdersInfo, State};
+use fixed::types::I80F48;
+
+/// Seconds between samples of `MarkCache.twap`.
+pub const TWAP_SAMPLE_PERIOD: u64 = 300;
+
+/// Seconds between funding payments, and window of `MarkCache.twap`.
+pub const FUNDING_PERIOD: u64 = 3600;
+
+/// Funding payments per day.
+pub const FUNDING_PERIODS_PER_DAY: u64 = 86_400 / FUNDING_PERIOD;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FundingEstimate {
+    /// `MarkCache.twap.cumul_avg`, in smol quote per smol asset.
+    pub mark_twap: I80F48,
+    /// `OracleCache.twap`, in smol quote per smol asset.
+    pub index_twap: I80F48,
+    /// Fraction of the index paid per funding period.
+    pub hourly_rate: I80F48,
+    /// Smol quote paid per smol asset held long.
+    pub funding_per_unit: I80F48,
+}
+
+impl FundingEstimate {
+    /// Estimate for the market at `index` into `State.perp_markets`,
+    /// `None` without an oracle TWAP.
+    pub fn new(state: &State, cache: &Cache, index: usize) -> Option<Self> {
+        let info = &state.perp_markets[index];
+        let mark_twap = I80F48::from(cache.marks[index].twap.cumul_avg);
+        let index_twap =
+            I80F48::from(cache.get_oracle(&info.oracle_symbol)?.twap);
+
+        if index_twap <= I80F48::ZERO {
+            return None;
+        }
+
+        let periods = I80F48::from_num(FUNDING_PERIODS_PER_DAY);
+        let funding_per_unit = (mark_twap - index_twap) / periods;
+
+        Some(Self {
+            mark_twap,
+            index_twap,
+            hourly_rate: funding_per_unit / index_twap,
+            funding_per_unit,
+        })
+    }
+
+    /// `hourly_rate` over a year, without compounding.
+    pub fn annualized_rate(&self) -> f64 {
+        self.hourly_rate.to_num::<f64>()
+            * (FUNDING_PERIODS_PER_DAY * 365) as f64
+    }
+
+    /// Smol quote `pos_size` smol asset pays at the next funding,
+    /// negative when it receives.
+    pub fn payment(&self, pos_size: i64) -> I80F48 {
+        I80F48::from_num(pos_size) * self.funding_per_unit
+    }
+}
+
+/// Unix timestamp `update_perp_funding` can next pay funding at.
+pub fn next_funding_time(market: &ZoDexMarket) -> u64 {
+    market.last_updated + FUNDING_PERIOD
+}
+
+/// Funding paid by a position since it was last settled, in smol quote,
+/// negative when it receives. `Cache.funding_cache` and
+/// `OpenOrdersInfo.funding_index` hold the cumulative funding per smol
+/// asset as `I80F48` bits.
+pub fn unsettled_funding(
+    cache: &Cache,
+    oo: &OpenOrdersInfo,
+    index: usize,
+) -> I80F48 {
+    let funding_cache = cache.funding_cache;
+    let delta = I80F48::from_bits(funding_cache[index])
+        - I80F48::from_bits(oo.funding_index);
+
+    I80F48::from_num(oo.pos_size) * delta
+}
@@ -0,0 +1,128 @@
+//! Client-side estimate of a margin account's health.
+//!
+//! This mirrors the program's checks closely enough for pre-flight
+//! validation and monitoring, but it does not account for unsettled
+//! funding, so it can be off by the funding accrued since the last
+//! `settle_funds`, see `funding::unsettled_funding`.
+
+use crate::{
+    Cache, Control, FractionType, Margin, OpenOrdersInfo, State,
+    SPOT_INITIAL_MARGIN_REQ, SPOT_MAINT_MARGIN_REQ,
+};
+use fixed::types::I80F48;
+
+/// Collateral balance in smol, applying the borrow cache multipliers.
+pub fn collateral_balance(cache: &Cache, margin: &Margin, i: usize) -> I80F48 {
+    let c = I80F48::from(margin.collateral[i]);
+    let b = &cache.borrow_cache[i];
+
+    match c.is_negative() {
+        true => c * I80F48::from(b.borrow_multiplier),
+        false => c * I80F48::from(b.supply_multiplier),
+    }
+}
+
+/// Oracle price of collateral `i` in smol quote per smol asset.
+pub fn collateral_price(state: &State, cache: &Cache, i: usize) -> I80F48 {
+    match cache.get_oracle(&state.collaterals[i].oracle_symbol) {
+        Some(o) => I80F48::from(o.price),
+        // The quote currency need not have an oracle.
+        None if i == 0 => I80F48::ONE,
+        None => I80F48::ZERO,
+    }
+}
+
+/// Margin fraction of a market for the given fraction type. The initial
+/// fraction is `base_imf`, maintenance half of it, and cancel halfway in
+/// between.
+pub fn margin_fraction(base_imf: u16, ty: FractionType) -> I80F48 {
+    let imf = I80F48::from_num(base_imf) / I80F48::from_num(1000);
+
+    match ty {
+        FractionType::Initial => imf,
+        FractionType::Cancel => imf * I80F48::from_num(3) / I80F48::from_num(4),
+        FractionType::Maintenance => imf / I80F48::from_num(2),
+    }
+}
+
+/// Unrealized pnl of a perp position at `mark`, in smol quote, including
+/// pnl realized on the dex but not yet settled into the margin.
+pub fn position_pnl(oo: &OpenOrdersInfo, mark: I80F48) -> I80F48 {
+    I80F48::from_num(oo.pos_size) * mark
+        + I80F48::from_num(oo.native_pc_total)
+        + I80F48::from_num(oo.realized_pnl)
+}
+
+/// Largest position the open orders could reach, in smol asset.
+pub fn position_exposure(oo: &OpenOrdersInfo) -> I80F48 {
+    let pos = I80F48::from_num(oo.pos_size);
+    let long = (pos + I80F48::from_num(oo.coin_on_bids)).abs();
+    let short = (pos - I80F48::from_num(oo.coin_on_asks)).abs();
+    long.max(short)
+}
+
+/// All values are in smol quote.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MarginHealth {
+    /// Weighted spot collateral plus unrealized perp pnl.
+    pub collateral: I80F48,
+    /// Requirement of borrows and perp exposure for the fraction type.
+    pub requirement: I80F48,
+}
+
+impl MarginHealth {
+    pub fn new(
+        state: &State,
+        cache: &Cache,
+        margin: &Margin,
+        control: &Control,
+        ty: FractionType,
+    ) -> Self {
+        let spot_req = match ty {
+            FractionType::Initial => SPOT_INITIAL_MARGIN_REQ,
+            FractionType::Cancel | FractionType::Maintenance => {
+                SPOT_MAINT_MARGIN_REQ
+            }
+        };
+        let spot_req = I80F48::from_num(spot_req - 1_000_000)
+            / I80F48::from_num(1_000_000);
+
+        let mut collateral = I80F48::ZERO;
+        let mut requirement = I80F48::ZERO;
+
+        for i in 0..state.total_collaterals as usize {
+            let value = collateral_balance(cache, margin, i)
+                * collateral_price(state, cache, i);
+
+            if value.is_negative() {
+                collateral += value;
+                requirement += -value * spot_req;
+            } else {
+                let weight = I80F48::from_num(state.collaterals[i].weight);
+                collateral += value * weight / I80F48::from_num(1000);
+            }
+        }
+
+        for i in 0..state.total_markets as usize {
+            let oo = &control.open_orders_agg[i];
+            let mark = I80F48::from(cache.marks[i].price);
+            let f = margin_fraction(state.perp_markets[i].base_imf, ty);
+
+            collateral += position_pnl(oo, mark);
+            requirement += position_exposure(oo) * mark * f;
+        }
+
+        Self {
+            collateral,
+            requirement,
+        }
+    }
+
+    pub fn free_collateral(&self) -> I80F48 {
+        self.collateral - self.requirement
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        !self.free_collateral().is_negative()
+    }
+}
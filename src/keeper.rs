@@ -0,0 +1,246 @@
+//! Plans which keeper cranks to send, most urgent first.
+//!
+//! ```ignore
+//! let view = KeeperView { state_key, state: &state, cache: &cache, ... };
+//! for task in plan(&view, &KeeperConfig::default()) {
+//!     match task.instruction(&keeper, &view) {
+//!         Some(ix) => send(ix),
+//!         // consume_events and crank_pnl need the event queue contents,
+//!         // see `crank::consume_events_ixs` and `crank::crank_pnl_ixs`.
+//!         None => send_event_cranks(&task),
+//!     }
+//! }
+//! ```
+
+use crate::{
+    crank::cache_oracle_ix,
+    dex::{EventQueueHeader, ZoDexMarket},
+    staleness::{StalenessReport, StalenessThresholds},
+    state_signer_address, Cache, State, Symbol, ZO_DEX_PID,
+};
+use anchor_lang::{prelude::Pubkey, InstructionData, ToAccountMetas};
+use solana_program::instruction::Instruction;
+
+#[derive(Copy, Clone, Debug)]
+pub struct KeeperConfig {
+    pub thresholds: StalenessThresholds,
+    /// Event count above which a queue is consumed.
+    pub event_queue_threshold: u64,
+    /// Symbols per `cache_oracle` instruction.
+    pub cache_oracle_batch: usize,
+}
+
+impl Default for KeeperConfig {
+    fn default() -> Self {
+        Self {
+            thresholds: StalenessThresholds::default(),
+            event_queue_threshold: 0,
+            cache_oracle_batch: 6,
+        }
+    }
+}
+
+/// Accounts the planner looks at. `event_queues` pairs each dex market
+/// with its event queue header.
+#[derive(Copy, Clone)]
+pub struct KeeperView<'a> {
+    pub state_key: Pubkey,
+    pub state: &'a State,
+    pub cache: &'a Cache,
+    pub markets: &'a [ZoDexMarket],
+    pub event_queues: &'a [(Pubkey, EventQueueHeader)],
+    /// Unix timestamp.
+    pub now: u64,
+}
+
+#[derive(Clone, Debug)]
+pub enum CrankReason {
+    StaleOracle { symbol: Symbol, age: u64 },
+    StaleMark { symbol: Symbol, age: u64 },
+    StaleBorrows { symbol: Symbol, age: u64 },
+    FundingOverdue { symbol: Symbol, age: u64 },
+    EventQueueBacklog { count: u64 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CrankAction {
+    CacheOracle { symbols: Vec<String> },
+    ConsumeEvents { dex_market: Pubkey },
+    CrankPnl { dex_market: Pubkey },
+    UpdatePerpFunding { dex_market: Pubkey },
+    CacheInterestRates { start: u8, end: u8 },
+}
+
+#[derive(Clone, Debug)]
+pub struct CrankTask {
+    pub action: CrankAction,
+    pub reasons: Vec<CrankReason>,
+}
+
+impl CrankTask {
+    /// Builds the instruction, except for `ConsumeEvents` and `CrankPnl`
+    /// which need the event queue contents.
+    pub fn instruction(
+        &self,
+        signer: &Pubkey,
+        view: &KeeperView,
+    ) -> Option<Instruction> {
+        let state = view.state_key;
+        let cache = view.state.cache;
+
+        match &self.action {
+            CrankAction::CacheOracle { symbols } => {
+                cache_oracle_ix(signer, &state, view.state, view.cache, symbols)
+                    .ok()
+            }
+            CrankAction::CacheInterestRates { start, end } => {
+                Some(Instruction {
+                    program_id: crate::ID,
+                    accounts: crate::accounts::CacheInterestRates {
+                        signer: *signer,
+                        state,
+                        cache,
+                    }
+                    .to_account_metas(None),
+                    data: crate::instruction::CacheInterestRates {
+                        start: *start,
+                        end: *end,
+                    }
+                    .data(),
+                })
+            }
+            CrankAction::UpdatePerpFunding { dex_market } => {
+                let m = view
+                    .markets
+                    .iter()
+                    .find(|m| m.own_address == *dex_market)?;
+
+                Some(Instruction {
+                    program_id: crate::ID,
+                    accounts: crate::accounts::UpdatePerpFunding {
+                        state,
+                        state_signer: state_signer_address(&state).0,
+                        cache,
+                        dex_market: *dex_market,
+                        market_bids: m.bids,
+                        market_asks: m.asks,
+                        dex_program: ZO_DEX_PID,
+                    }
+                    .to_account_metas(None),
+                    data: crate::instruction::UpdatePerpFunding {}.data(),
+                })
+            }
+            CrankAction::ConsumeEvents { .. }
+            | CrankAction::CrankPnl { .. } => None,
+        }
+    }
+}
+
+/// Cranks due for `view`, in order of urgency: oracles first since
+/// health depends on them, then event queues, funding and interest rates.
+pub fn plan(view: &KeeperView, config: &KeeperConfig) -> Vec<CrankTask> {
+    let report = StalenessReport::new(
+        view.state,
+        view.cache,
+        view.markets,
+        view.now,
+        &config.thresholds,
+    );
+    let mut r: Vec<CrankTask> = Vec::new();
+
+    // Oldest first, so the worst offenders go in the first batch.
+    let mut oracles: Vec<(Symbol, u64, CrankReason)> = report
+        .oracles
+        .iter()
+        .map(|x| {
+            let reason = CrankReason::StaleOracle {
+                symbol: x.symbol,
+                age: x.age,
+            };
+            (x.symbol, x.age, reason)
+        })
+        .chain(report.marks.iter().map(|x| {
+            let reason = CrankReason::StaleMark {
+                symbol: x.symbol,
+                age: x.age,
+            };
+            (x.oracle_symbol, x.age, reason)
+        }))
+        .collect();
+    oracles.sort_by_key(|(_, age, _)| std::cmp::Reverse(*age));
+
+    let mut symbols: Vec<Symbol> = Vec::new();
+    for (s, _, _) in oracles.iter() {
+        if !symbols.contains(s) {
+            symbols.push(*s);
+        }
+    }
+    for batch in symbols.chunks(config.cache_oracle_batch.max(1)) {
+        r.push(CrankTask {
+            action: CrankAction::CacheOracle {
+                symbols: batch.iter().map(String::from).collect(),
+            },
+            reasons: oracles
+                .iter()
+                .filter(|(s, _, _)| batch.contains(s))
+                .map(|(_, _, reason)| reason.clone())
+                .collect(),
+        });
+    }
+
+    let mut queues: Vec<&(Pubkey, EventQueueHeader)> = view
+        .event_queues
+        .iter()
+        .filter(|(_, h)| h.count > config.event_queue_threshold)
+        .collect();
+    queues.sort_by_key(|(_, h)| std::cmp::Reverse(h.count));
+
+    for (dex_market, h) in queues.iter() {
+        let reasons = vec![CrankReason::EventQueueBacklog { count: h.count }];
+
+        r.push(CrankTask {
+            action: CrankAction::ConsumeEvents {
+                dex_market: *dex_market,
+            },
+            reasons: reasons.clone(),
+        });
+        r.push(CrankTask {
+            action: CrankAction::CrankPnl {
+                dex_market: *dex_market,
+            },
+            reasons,
+        });
+    }
+
+    let mut funding = report.funding.clone();
+    funding.sort_by_key(|x| std::cmp::Reverse(x.age));
+
+    for x in funding.iter() {
+        r.push(CrankTask {
+            action: CrankAction::UpdatePerpFunding {
+                dex_market: x.dex_market,
+            },
+            reasons: vec![CrankReason::FundingOverdue {
+                symbol: x.symbol,
+                age: x.age,
+            }],
+        });
+    }
+
+    for (start, end) in report.cache_interest_rates_ranges() {
+        r.push(CrankTask {
+            action: CrankAction::CacheInterestRates { start, end },
+            reasons: report
+                .borrows
+                .iter()
+                .filter(|x| (start..end).contains(&(x.index as u8)))
+                .map(|x| CrankReason::StaleBorrows {
+                    symbol: x.symbol,
+                    age: x.age,
+                })
+                .collect(),
+        });
+    }
+
+    r
+}
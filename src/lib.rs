@@ -5,8 +5,31 @@ mod util;
 use anchor_lang::prelude::*;
 use solana_program::pubkey;
 
+pub mod book;
+#[cfg(feature = "cpi")]
+pub mod cpi_helpers;
+pub mod crank;
 pub mod dex;
+pub mod diff;
+pub mod error;
 pub mod events;
+pub mod fees;
+pub mod funding;
+pub mod health;
+pub mod keeper;
+pub mod oracle;
+pub mod preflight;
+pub mod pricing;
+pub mod replay;
+pub mod serum;
+pub mod snapshot;
+pub mod special_orders;
+pub mod staleness;
+pub mod swap;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod trades;
+pub mod twap;
 pub use crate::types::*;
 pub use crate::util::*;
 
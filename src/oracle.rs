@@ -0,0 +1,258 @@
+//! Price accounts read by `cache_oracle`, normalized to the units of
+//! `OracleCache`.
+
+use crate::{OracleCache, OracleType};
+use anchor_lang::prelude::Pubkey;
+use bytemuck::{Pod, PodCastError, Zeroable};
+use fixed::types::I80F48;
+use std::mem::size_of;
+
+/// Oracle reading in smol quote per smol asset.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OraclePrice {
+    pub price: I80F48,
+    pub confidence: I80F48,
+    /// Exponent of the raw price, i.e. `-scale` for switchboard.
+    pub expo: i32,
+    /// Unix timestamp.
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Relative difference from the cached price, in bps.
+    pub fn divergence_bps(&self, cache: &OracleCache) -> Option<i64> {
+        let cached = I80F48::from(cache.price);
+        (self.price - cached)
+            .checked_div(cached)?
+            .checked_mul(I80F48::from_num(10_000))?
+            .checked_to_num()
+    }
+}
+
+/// `m * 10^exp` as an `I80F48`, dropping digits beyond its precision.
+fn scale(mut m: i128, mut exp: i32) -> Option<I80F48> {
+    while exp < -23 || I80F48::checked_from_num(m).is_none() {
+        if exp >= 0 {
+            return None;
+        }
+        m /= 10;
+        exp += 1;
+    }
+
+    let v = I80F48::from_num(m);
+    let p = I80F48::checked_from_num(10i128.checked_pow(exp.unsigned_abs())?)?;
+
+    match exp >= 0 {
+        true => v.checked_mul(p),
+        false => v.checked_div(p),
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OracleError {
+    /// Too short for the account layout.
+    Pod(PodCastError),
+    /// Wrong magic, version or discriminator for the `OracleType`.
+    InvalidAccount,
+}
+
+impl From<PodCastError> for OracleError {
+    fn from(e: PodCastError) -> Self {
+        Self::Pod(e)
+    }
+}
+
+/// Header of a pyth v2 price account, up to the aggregate price.
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+pub struct PythPrice {
+    pub magic: u32,
+    pub ver: u32,
+    pub atype: u32,
+    pub size: u32,
+    pub ptype: u32,
+    pub expo: i32,
+    pub num: u32,
+    pub num_qt: u32,
+    pub last_slot: u64,
+    pub valid_slot: u64,
+    pub ema_price: [i64; 3],
+    pub ema_conf: [i64; 3],
+    pub timestamp: i64,
+    pub min_pub: u8,
+    _drv: [u8; 7],
+    pub prod: Pubkey,
+    pub next: Pubkey,
+    pub prev_slot: u64,
+    pub prev_price: i64,
+    pub prev_conf: u64,
+    pub prev_timestamp: i64,
+    pub agg_price: i64,
+    pub agg_conf: u64,
+    pub agg_status: u32,
+    pub agg_corp_act: u32,
+    pub agg_pub_slot: u64,
+}
+
+unsafe impl Zeroable for PythPrice {}
+unsafe impl Pod for PythPrice {}
+
+impl PythPrice {
+    /// Whether `deserialize` accepts `buf` without panicking.
+    pub fn is_valid(buf: &[u8]) -> bool {
+        match buf.get(..size_of::<Self>()) {
+            Some(x) => match bytemuck::try_from_bytes::<Self>(x) {
+                Ok(r) => r.magic == 0xa1b2c3d4 && r.ver == 2 && r.atype == 3,
+                Err(_) => false,
+            },
+            None => false,
+        }
+    }
+
+    pub fn deserialize(buf: &[u8]) -> Result<&Self, PodCastError> {
+        if buf.len() < size_of::<Self>() {
+            return Err(PodCastError::SizeMismatch);
+        }
+
+        let r: &Self = bytemuck::try_from_bytes(&buf[..size_of::<Self>()])?;
+
+        if r.magic != 0xa1b2c3d4 || r.ver != 2 || r.atype != 3 {
+            panic!("Invalid buffer for pyth price");
+        }
+
+        Ok(r)
+    }
+
+    pub fn is_trading(&self) -> bool {
+        self.agg_status == 1
+    }
+
+    pub fn price(
+        &self,
+        base_decimals: u8,
+        quote_decimals: u8,
+    ) -> Option<OraclePrice> {
+        let exp = self.expo + quote_decimals as i32 - base_decimals as i32;
+
+        Some(OraclePrice {
+            price: scale(self.agg_price as i128, exp)?,
+            confidence: scale(self.agg_conf as i128, exp)?,
+            expo: self.expo,
+            publish_time: self.timestamp,
+        })
+    }
+}
+
+/// Header of a switchboard v2 aggregator account, up to the latest
+/// confirmed round's standard deviation.
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+pub struct SwitchboardAggregator {
+    _discriminator: [u8; 8],
+
+    pub name: [u8; 32],
+    pub metadata: [u8; 128],
+    _reserved1: [u8; 32],
+    pub queue_pubkey: Pubkey,
+    pub oracle_request_batch_size: u32,
+    pub min_oracle_results: u32,
+    pub min_job_results: u32,
+    pub min_update_delay_seconds: u32,
+    pub start_after: i64,
+    pub variance_threshold_mantissa: i128,
+    pub variance_threshold_scale: u32,
+    pub force_report_period: i64,
+    pub expiration: i64,
+    pub consecutive_failure_count: u64,
+    pub next_allowed_update_time: i64,
+    pub is_locked: u8,
+    pub crank_pubkey: Pubkey,
+
+    // latest_confirmed_round
+    pub num_success: u32,
+    pub num_error: u32,
+    pub is_closed: u8,
+    pub round_open_slot: u64,
+    pub round_open_timestamp: i64,
+    pub result_mantissa: i128,
+    pub result_scale: u32,
+    pub std_deviation_mantissa: i128,
+    pub std_deviation_scale: u32,
+}
+
+unsafe impl Zeroable for SwitchboardAggregator {}
+unsafe impl Pod for SwitchboardAggregator {}
+
+impl SwitchboardAggregator {
+    const DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+    /// Whether `deserialize` accepts `buf` without panicking.
+    pub fn is_valid(buf: &[u8]) -> bool {
+        buf.len() >= size_of::<Self>() && buf[..8] == Self::DISCRIMINATOR
+    }
+
+    pub fn deserialize(buf: &[u8]) -> Result<&Self, PodCastError> {
+        if buf.len() < size_of::<Self>() {
+            return Err(PodCastError::SizeMismatch);
+        }
+
+        let r: &Self = bytemuck::try_from_bytes(&buf[..size_of::<Self>()])?;
+
+        if r._discriminator != Self::DISCRIMINATOR {
+            panic!("Invalid buffer for switchboard aggregator");
+        }
+
+        Ok(r)
+    }
+
+    pub fn price(
+        &self,
+        base_decimals: u8,
+        quote_decimals: u8,
+    ) -> Option<OraclePrice> {
+        let d = quote_decimals as i32 - base_decimals as i32;
+
+        Some(OraclePrice {
+            price: scale(self.result_mantissa, d - self.result_scale as i32)?,
+            confidence: scale(
+                self.std_deviation_mantissa,
+                d - self.std_deviation_scale as i32,
+            )?,
+            expo: -(self.result_scale as i32),
+            publish_time: self.round_open_timestamp,
+        })
+    }
+}
+
+/// Parses the account of an `OracleSource` of the given type. Returns
+/// `None` for `OracleType::Nil` or if the price overflows, and an error
+/// if `buf` is not an account of that type.
+pub fn parse_oracle(
+    ty: OracleType,
+    buf: &[u8],
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Result<Option<OraclePrice>, OracleError> {
+    Ok(match ty {
+        OracleType::Nil => None,
+        OracleType::Pyth => {
+            if buf.len() < size_of::<PythPrice>() {
+                return Err(PodCastError::SizeMismatch.into());
+            }
+            if !PythPrice::is_valid(buf) {
+                return Err(OracleError::InvalidAccount);
+            }
+            PythPrice::deserialize(buf)?.price(base_decimals, quote_decimals)
+        }
+        OracleType::Switchboard => {
+            if buf.len() < size_of::<SwitchboardAggregator>() {
+                return Err(PodCastError::SizeMismatch.into());
+            }
+            if !SwitchboardAggregator::is_valid(buf) {
+                return Err(OracleError::InvalidAccount);
+            }
+            SwitchboardAggregator::deserialize(buf)?
+                .price(base_decimals, quote_decimals)
+        }
+    })
+}
@@ -0,0 +1,174 @@
+//! Checks shared by `place_perp_order`, `place_perp_order_with_max_ts`
+//! and `place_perp_order_lite` before signing.
+
+use crate::{
+    dex::{Slab, ZoDexMarket},
+    health::MarginHealth,
+    Cache, Control, FractionType, Margin, OrderType, State,
+};
+
+/// Order in big units, as a user would enter it.
+#[derive(Copy, Clone, Debug)]
+pub struct OrderRequest {
+    pub is_long: bool,
+    /// Big quote per big asset.
+    pub price: f64,
+    /// Big asset.
+    pub size: f64,
+    pub order_type: OrderType,
+    /// Smol quote, defaults to the full notional at the limit price.
+    pub max_quote_quantity: Option<u64>,
+}
+
+/// Accounts of the trader placing the order.
+#[derive(Copy, Clone)]
+pub struct TraderAccounts<'a> {
+    pub state: &'a State,
+    pub cache: &'a Cache,
+    pub margin: &'a Margin,
+    pub control: &'a Control,
+    /// Index of the order's market into `State.perp_markets`.
+    pub market_index: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OrderError {
+    /// The price rounds to zero lots.
+    ZeroPrice,
+    /// The size rounds to zero lots.
+    ZeroSize,
+    /// A reduce only order on the same side as the position, or without
+    /// a position.
+    NotReducing,
+    /// A post only order that would match immediately.
+    PostOnlyWouldCross,
+    /// `max_quote_quantity` is zero.
+    ZeroQuoteQuantity,
+    /// The book passed is not the side the order matches against.
+    WrongBookSide,
+    /// Free collateral would be negative at initial margin.
+    InsufficientMargin,
+    /// The notional or size in smol does not fit in a `u64`.
+    Overflow,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OrderWarning {
+    /// The reduce only order is larger than the position and is clipped.
+    ReduceOnlyExceedsPosition,
+    /// `max_quote_quantity` caps the order below `max_base_quantity`.
+    QuoteQuantityLimitsSize,
+}
+
+/// Arguments to pass to the place instructions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderPreflight {
+    /// In price lots.
+    pub limit_price: u64,
+    /// In base lots.
+    pub max_base_quantity: u64,
+    /// In smol quote.
+    pub max_quote_quantity: u64,
+    pub warnings: Vec<OrderWarning>,
+}
+
+/// Validates `req`, with `opposite` being the side of the book the order
+/// matches against, i.e. the asks for a long.
+pub fn preflight_order(
+    req: &OrderRequest,
+    market: &ZoDexMarket,
+    opposite: &Slab,
+    accounts: &TraderAccounts,
+) -> Result<OrderPreflight, OrderError> {
+    let mut warnings: Vec<OrderWarning> = Vec::new();
+
+    let limit_price = market.price_to_lots(req.price);
+    let max_base_quantity = market.size_to_lots(req.size);
+
+    if limit_price == 0 {
+        return Err(OrderError::ZeroPrice);
+    }
+    if max_base_quantity == 0 {
+        return Err(OrderError::ZeroSize);
+    }
+    if opposite.is_bids() == req.is_long {
+        return Err(OrderError::WrongBookSide);
+    }
+
+    let full_quote = limit_price
+        .checked_mul(max_base_quantity)
+        .and_then(|x| x.checked_mul(market.pc_lot_size))
+        .ok_or(OrderError::Overflow)?;
+    let max_quote_quantity = req.max_quote_quantity.unwrap_or(full_quote);
+    if max_quote_quantity == 0 {
+        return Err(OrderError::ZeroQuoteQuantity);
+    }
+    if max_quote_quantity < full_quote {
+        warnings.push(OrderWarning::QuoteQuantityLimitsSize);
+    }
+
+    let oo = &accounts.control.open_orders_agg[accounts.market_index];
+    let pos_size = oo.pos_size;
+    let native_size = max_base_quantity
+        .checked_mul(market.coin_lot_size)
+        .ok_or(OrderError::Overflow)?;
+
+    match req.order_type {
+        OrderType::ReduceOnlyIoc | OrderType::ReduceOnlyLimit => {
+            if pos_size == 0 || (pos_size > 0) == req.is_long {
+                return Err(OrderError::NotReducing);
+            }
+            if native_size > pos_size.unsigned_abs() {
+                warnings.push(OrderWarning::ReduceOnlyExceedsPosition);
+            }
+        }
+        OrderType::PostOnly => {
+            let crosses = opposite.get_best().map(|x| {
+                let best = x.price().get();
+                match req.is_long {
+                    true => best <= limit_price,
+                    false => best >= limit_price,
+                }
+            });
+            if crosses == Some(true) {
+                return Err(OrderError::PostOnlyWouldCross);
+            }
+        }
+        _ => {}
+    }
+
+    // Resting orders count against initial margin, so add this one to the
+    // open orders before computing health.
+    let mut control = *accounts.control;
+    let oo = &mut control.open_orders_agg[accounts.market_index];
+    match req.is_long {
+        true => {
+            oo.coin_on_bids = { oo.coin_on_bids }
+                .checked_add(native_size)
+                .ok_or(OrderError::Overflow)?;
+        }
+        false => {
+            oo.coin_on_asks = { oo.coin_on_asks }
+                .checked_add(native_size)
+                .ok_or(OrderError::Overflow)?;
+        }
+    }
+
+    let health = MarginHealth::new(
+        accounts.state,
+        accounts.cache,
+        accounts.margin,
+        &control,
+        FractionType::Initial,
+    );
+    if !health.is_healthy() {
+        return Err(OrderError::InsufficientMargin);
+    }
+
+    Ok(OrderPreflight {
+        limit_price,
+        max_base_quantity,
+        max_quote_quantity,
+        warnings,
+    })
+}
@@ -0,0 +1,161 @@
+//! Theoretical marks of the perp types, with delta and gamma.
+//!
+//! Prices are in big quote per big asset. Everlasting options use the
+//! closed form for an option whose funding pays out continuously over
+//! `funding_period`, and square perps the expectation of `S²` under the
+//! same funding. Both assume lognormal prices with no drift.
+
+use crate::{Cache, PerpMarketInfo, PerpType};
+use fixed::types::I80F48;
+
+#[derive(Copy, Clone, Debug)]
+pub struct PricingParams {
+    /// Annualized volatility, e.g. `0.8` for 80%.
+    pub volatility: f64,
+    /// Average time between funding payments in years, e.g. `1. / 365.`
+    /// for daily funding.
+    pub funding_period: f64,
+}
+
+impl PricingParams {
+    fn variance(&self) -> Option<f64> {
+        let v = self.volatility * self.volatility * self.funding_period;
+        (v.is_finite() && v > 0.).then_some(v)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Valuation {
+    pub mark: f64,
+    /// Change of `mark` per unit of spot.
+    pub delta: f64,
+    /// Change of `delta` per unit of spot.
+    pub gamma: f64,
+}
+
+/// Term `K/u * (S/K)^a` shared by calls and puts, with its derivatives.
+fn power_term(spot: f64, strike: f64, u: f64, a: f64) -> Valuation {
+    let x = spot / strike;
+
+    Valuation {
+        mark: strike / u * x.powf(a),
+        delta: a / u * x.powf(a - 1.),
+        gamma: a * (a - 1.) / (u * strike) * x.powf(a - 2.),
+    }
+}
+
+fn everlasting_u(params: &PricingParams) -> Option<f64> {
+    Some((1. + 8. / params.variance()?).sqrt())
+}
+
+fn valid_price(x: f64) -> bool {
+    x.is_finite() && x > 0.
+}
+
+/// Everlasting call, `None` if the spot, strike or parameters are not
+/// positive.
+pub fn everlasting_call(
+    spot: f64,
+    strike: f64,
+    params: &PricingParams,
+) -> Option<Valuation> {
+    if !valid_price(spot) || !valid_price(strike) {
+        return None;
+    }
+    let u = everlasting_u(params)?;
+
+    Some(match spot <= strike {
+        true => power_term(spot, strike, u, (1. + u) / 2.),
+        false => {
+            let t = power_term(spot, strike, u, (1. - u) / 2.);
+            Valuation {
+                mark: spot - strike + t.mark,
+                delta: 1. + t.delta,
+                gamma: t.gamma,
+            }
+        }
+    })
+}
+
+/// Everlasting put, by parity with the call: `C - P = S - K`.
+pub fn everlasting_put(
+    spot: f64,
+    strike: f64,
+    params: &PricingParams,
+) -> Option<Valuation> {
+    let c = everlasting_call(spot, strike, params)?;
+
+    Some(Valuation {
+        mark: c.mark - spot + strike,
+        delta: c.delta - 1.,
+        gamma: c.gamma,
+    })
+}
+
+/// Square perp, `S² / (1 - σ²T)` divided by `norm`. `None` if `σ²T`
+/// is not below 1, where the funding does not converge.
+pub fn square_perp(
+    spot: f64,
+    norm: f64,
+    params: &PricingParams,
+) -> Option<Valuation> {
+    if !valid_price(spot) || !valid_price(norm) {
+        return None;
+    }
+    let v = params.variance()?;
+    if v >= 1. {
+        return None;
+    }
+    let m = 1. / ((1. - v) * norm);
+
+    Some(Valuation {
+        mark: spot * spot * m,
+        delta: 2. * spot * m,
+        gamma: 2. * m,
+    })
+}
+
+/// Mark of a market of `perp_type` with `strike` in smol quote per big
+/// asset. Futures track spot. Square perps are normalized by the strike
+/// when it is nonzero.
+pub fn fair_value(
+    perp_type: PerpType,
+    spot: f64,
+    strike: u64,
+    params: &PricingParams,
+) -> Option<Valuation> {
+    let strike = strike as f64 / 1e6;
+
+    match perp_type {
+        PerpType::Future => valid_price(spot).then_some(Valuation {
+            mark: spot,
+            delta: 1.,
+            gamma: 0.,
+        }),
+        PerpType::CallOption => everlasting_call(spot, strike, params),
+        PerpType::PutOption => everlasting_put(spot, strike, params),
+        PerpType::Square => match strike > 0. {
+            true => square_perp(spot, strike, params),
+            false => square_perp(spot, 1., params),
+        },
+    }
+}
+
+/// Cached oracle price of the market's underlying in big quote per big
+/// asset.
+pub fn oracle_spot(cache: &Cache, info: &PerpMarketInfo) -> Option<f64> {
+    let o = cache.get_oracle(&info.oracle_symbol)?;
+    let adj = 10f64.powi(o.base_decimals as i32 - o.quote_decimals as i32);
+
+    Some(I80F48::from(o.price).to_num::<f64>() * adj)
+}
+
+/// `fair_value` of a market at its cached oracle price.
+pub fn market_fair_value(
+    cache: &Cache,
+    info: &PerpMarketInfo,
+    params: &PricingParams,
+) -> Option<Valuation> {
+    let spot = oracle_spot(cache, info)?;
+    fair_value(info.perp_type, spot, info.strike, params)
+}
@@ -0,0 +1,269 @@
+//! Rebuilds the history of a margin from its program logs.
+//!
+//! Balances move with deposits, withdrawals, swaps, spot liquidations
+//! and bankruptcies. Perp fills, OTC fills and perp liquidations move
+//! positions, fees included, and realized pnl moves from the position's
+//! quote into the quote collateral, index 0. Interest and funding are not
+//! logged, so they are not part of the history either.
+//!
+//! ```ignore
+//! let mut replay = Replay::new(margin_key, &state);
+//! for (timestamp, event) in decode_logs(transactions) {
+//!     replay.apply(timestamp, &event)?;
+//! }
+//! println!("{:?}", replay.ledger);
+//! ```
+
+use crate::{
+    events::{
+        BankruptcyLog, DepositLog, EventFillLog, LiquidationEvent,
+        LiquidationLog, OtcFill, RealizedPnlLog, SwapLog, WithdrawLog,
+    },
+    State,
+};
+use anchor_lang::prelude::Pubkey;
+use std::collections::BTreeMap;
+
+/// Log of the zo program or dex concerning margins.
+pub enum MarginEvent {
+    Deposit(DepositLog),
+    Withdraw(WithdrawLog),
+    Swap(SwapLog),
+    RealizedPnl(RealizedPnlLog),
+    Fill(EventFillLog),
+    Liquidation(LiquidationLog),
+    Bankruptcy(BankruptcyLog),
+    OtcFill(OtcFill),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MarginEventKind {
+    Deposit,
+    Withdraw,
+    Swap,
+    RealizedPnl,
+    Fill,
+    Liquidation,
+    Bankruptcy,
+    OtcFill,
+}
+
+impl MarginEvent {
+    pub fn kind(&self) -> MarginEventKind {
+        match self {
+            Self::Deposit(_) => MarginEventKind::Deposit,
+            Self::Withdraw(_) => MarginEventKind::Withdraw,
+            Self::Swap(_) => MarginEventKind::Swap,
+            Self::RealizedPnl(_) => MarginEventKind::RealizedPnl,
+            Self::Fill(_) => MarginEventKind::Fill,
+            Self::Liquidation(_) => MarginEventKind::Liquidation,
+            Self::Bankruptcy(_) => MarginEventKind::Bankruptcy,
+            Self::OtcFill(_) => MarginEventKind::OtcFill,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    UnknownCollateral(String),
+    UnknownMarket(String),
+    UnknownDexMarket(Pubkey),
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Position {
+    /// Smol asset, negative when short.
+    pub size: i64,
+    /// Smol quote received minus paid, fees included, and less the
+    /// realized pnl, like `OpenOrdersInfo.native_pc_total`.
+    pub quote: i64,
+    /// Sum of `RealizedPnlLog.pnl`, in smol quote, moved from `quote` to
+    /// the balance of collateral 0.
+    pub realized_pnl: i64,
+    /// Taker fees paid minus maker rebates, in smol quote. Already part
+    /// of `quote`.
+    pub fees: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ledger {
+    /// Smol, by index into `State.collaterals`.
+    pub balances: BTreeMap<usize, i64>,
+    /// By index into `State.perp_markets`.
+    pub positions: BTreeMap<usize, Position>,
+}
+
+impl Ledger {
+    pub fn realized_pnl(&self) -> i64 {
+        self.positions.values().map(|x| x.realized_pnl).sum()
+    }
+
+    pub fn fees(&self) -> i64 {
+        self.positions.values().map(|x| x.fees).sum()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub kind: MarginEventKind,
+    /// Ledger after the event.
+    pub ledger: Ledger,
+}
+
+pub struct Replay<'a> {
+    pub margin: Pubkey,
+    state: &'a State,
+    pub ledger: Ledger,
+    pub history: Vec<HistoryEntry>,
+}
+
+impl<'a> Replay<'a> {
+    /// `state` maps the symbols and dex markets of the logs to indices.
+    pub fn new(margin: Pubkey, state: &'a State) -> Self {
+        Self {
+            margin,
+            state,
+            ledger: Ledger::default(),
+            history: Vec::new(),
+        }
+    }
+
+    fn collateral(&self, symbol: &str) -> Result<usize, ReplayError> {
+        self.state.collaterals[..self.state.total_collaterals as usize]
+            .iter()
+            .position(|x| String::from(x.oracle_symbol) == symbol)
+            .ok_or_else(|| ReplayError::UnknownCollateral(symbol.into()))
+    }
+
+    fn market(&self, symbol: &str) -> Result<usize, ReplayError> {
+        self.state.perp_markets[..self.state.total_markets as usize]
+            .iter()
+            .position(|x| String::from(x.symbol) == symbol)
+            .ok_or_else(|| ReplayError::UnknownMarket(symbol.into()))
+    }
+
+    fn dex_market(&self, key: &Pubkey) -> Result<usize, ReplayError> {
+        self.state.perp_markets[..self.state.total_markets as usize]
+            .iter()
+            .position(|x| x.dex_market == *key)
+            .ok_or(ReplayError::UnknownDexMarket(*key))
+    }
+
+    fn balance(&mut self, index: usize) -> &mut i64 {
+        self.ledger.balances.entry(index).or_default()
+    }
+
+    fn position(&mut self, index: usize) -> &mut Position {
+        self.ledger.positions.entry(index).or_default()
+    }
+
+    /// Applies `event` if it concerns the margin, and records the ledger
+    /// after it. Returns whether it did.
+    pub fn apply(
+        &mut self,
+        timestamp: u64,
+        event: &MarginEvent,
+    ) -> Result<bool, ReplayError> {
+        let m = self.margin;
+
+        match event {
+            MarginEvent::Deposit(x) if x.margin_key == m => {
+                *self.balance(x.col_index as usize) += x.deposit_amount as i64;
+            }
+            MarginEvent::Withdraw(x) if x.margin_key == m => {
+                *self.balance(x.col_index as usize) -= x.withdraw_amount as i64;
+            }
+            MarginEvent::Swap(x) if x.margin_key == m => {
+                *self.balance(x.base_index as usize) += x.base_delta;
+                *self.balance(x.quote_index as usize) += x.quote_delta;
+            }
+            MarginEvent::RealizedPnl(x) if x.margin == m => {
+                let i = self.dex_market(&x.market_key)?;
+                let p = self.position(i);
+                p.realized_pnl += x.pnl;
+                p.quote -= x.pnl;
+                *self.balance(0) += x.pnl;
+            }
+            MarginEvent::Fill(x) if x.margin == m => {
+                let i = self.dex_market(&x.market_key)?;
+                let fee = x.fee_or_rebate as i64;
+                let p = self.position(i);
+
+                // The quote paid or received is net of fees.
+                match x.is_long {
+                    true => {
+                        p.size += x.qty_received as i64;
+                        p.quote -= x.qty_paid as i64;
+                    }
+                    false => {
+                        p.size -= x.qty_paid as i64;
+                        p.quote += x.qty_received as i64;
+                    }
+                }
+                p.fees += match x.is_maker {
+                    true => -fee,
+                    false => fee,
+                };
+            }
+            MarginEvent::OtcFill(x)
+                if x.taker_margin == m || x.maker_margin == m =>
+            {
+                let i = self.dex_market(&x.market)?;
+                let sign = if x.taker_margin == m { 1 } else { -1 };
+                let p = self.position(i);
+                p.size += sign * x.d_base;
+                p.quote += sign * x.d_quote;
+            }
+            MarginEvent::Liquidation(x)
+                if x.liqor_margin == m || x.liqee_margin == m =>
+            {
+                let sign = if x.liqor_margin == m { 1 } else { -1 };
+                match x.liquidation_event {
+                    LiquidationEvent::Perp => {
+                        let i = self.market(&x.base_symbol)?;
+                        let p = self.position(i);
+                        p.size += sign * x.assets_to_liqor;
+                        p.quote += sign * x.quote_to_liqor;
+                    }
+                    LiquidationEvent::Spot => {
+                        let base = self.collateral(&x.base_symbol)?;
+                        let quote = match &x.quote_symbol {
+                            Some(s) => self.collateral(s)?,
+                            None => 0,
+                        };
+                        *self.balance(base) += sign * x.assets_to_liqor;
+                        *self.balance(quote) += sign * x.quote_to_liqor;
+                    }
+                }
+            }
+            MarginEvent::Bankruptcy(x)
+                if x.liqor_margin == m || x.liqee_margin == m =>
+            {
+                let sign = if x.liqor_margin == m { 1 } else { -1 };
+                let base = self.collateral(&x.base_symbol)?;
+                *self.balance(base) += sign * x.assets_to_liqor;
+                *self.balance(0) += sign * x.quote_to_liqor;
+            }
+            _ => return Ok(false),
+        }
+
+        self.history.push(HistoryEntry {
+            timestamp,
+            kind: event.kind(),
+            ledger: self.ledger.clone(),
+        });
+        Ok(true)
+    }
+
+    /// Applies `events`, which must be in time order.
+    pub fn apply_all(
+        &mut self,
+        events: impl IntoIterator<Item = (u64, MarginEvent)>,
+    ) -> Result<(), ReplayError> {
+        for (timestamp, event) in events {
+            self.apply(timestamp, &event)?;
+        }
+        Ok(())
+    }
+}
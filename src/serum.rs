@@ -0,0 +1,174 @@
+//! Serum v3 accounts used by the `swap` instruction.
+//!
+//! Serum order books and event queues share their layout with the zo dex,
+//! so `dex::Slab` and `dex::Event` decode them as is. The `control` field
+//! of their leaves and events holds the serum open orders account instead.
+
+use crate::{
+    dex::{AccountFlag, Slab},
+    SERUM_DEX_PID,
+};
+use anchor_lang::prelude::Pubkey;
+use bytemuck::{Pod, PodCastError, Zeroable};
+
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+pub struct SerumMarket {
+    _head_pad: [u8; 5],
+
+    pub account_flags: u64,
+    pub own_address: Pubkey,
+    pub vault_signer_nonce: u64,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub coin_deposits_total: u64,
+    pub coin_fees_accrued: u64,
+    pub pc_vault: Pubkey,
+    pub pc_deposits_total: u64,
+    pub pc_fees_accrued: u64,
+    pub pc_dust_threshold: u64,
+    pub req_q: Pubkey,
+    pub event_q: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub fee_rate_bps: u64,
+    pub referrer_rebates_accrued: u64,
+
+    _tail_pad: [u8; 7],
+}
+
+unsafe impl Zeroable for SerumMarket {}
+unsafe impl Pod for SerumMarket {}
+
+impl SerumMarket {
+    pub fn deserialize(buf: &[u8]) -> Result<&Self, PodCastError> {
+        const FLAGS: u64 =
+            (AccountFlag::Initialized as u64) | (AccountFlag::Market as u64);
+
+        let r: &Self = bytemuck::try_from_bytes(buf)?;
+
+        if r._head_pad[..] != *"serum".as_bytes()
+            || r._tail_pad[..] != *"padding".as_bytes()
+            || r.account_flags & FLAGS != FLAGS
+        {
+            panic!("Invalid buffer for serum market");
+        }
+
+        Ok(r)
+    }
+
+    /// The `serum_vault_signer` account passed to `swap`.
+    pub fn vault_signer(&self) -> Pubkey {
+        Pubkey::create_program_address(
+            &[
+                self.own_address.as_ref(),
+                &self.vault_signer_nonce.to_le_bytes(),
+            ],
+            &SERUM_DEX_PID,
+        )
+        .unwrap()
+    }
+
+    /// Price in lots to smol quote per smol coin.
+    pub fn lots_to_native_price(self, n: u64) -> f64 {
+        (n * self.pc_lot_size) as f64 / self.coin_lot_size as f64
+    }
+
+    /// Size in lots to smol coin.
+    pub fn lots_to_native_size(self, n: u64) -> u64 {
+        n * self.coin_lot_size
+    }
+
+    /// Price in lots to big quote per big coin.
+    pub fn lots_to_price(
+        self,
+        n: u64,
+        coin_decimals: u8,
+        pc_decimals: u8,
+    ) -> f64 {
+        self.lots_to_native_price(n)
+            * 10f64.powi(coin_decimals as i32 - pc_decimals as i32)
+    }
+
+    /// Size in lots to big coin.
+    pub fn lots_to_size(self, n: u64, coin_decimals: u8) -> f64 {
+        self.lots_to_native_size(n) as f64 / 10f64.powi(coin_decimals as i32)
+    }
+
+    /// Deserializes `bids` or `asks`, checking that it belongs to this
+    /// market.
+    pub fn load_book<'a>(
+        &self,
+        key: &Pubkey,
+        buf: &'a [u8],
+    ) -> Result<Slab<'a>, PodCastError> {
+        if *key != self.bids && *key != self.asks {
+            panic!("Book does not belong to serum market");
+        }
+
+        let slab = Slab::deserialize(buf)?;
+        if slab.is_bids() != (*key == self.bids) {
+            panic!("Book side does not match serum market");
+        }
+
+        Ok(slab)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+#[repr(packed)]
+pub struct SerumOpenOrders {
+    _head_pad: [u8; 5],
+
+    pub account_flags: u64,
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub native_coin_free: u64,
+    pub native_coin_total: u64,
+    pub native_pc_free: u64,
+    pub native_pc_total: u64,
+    pub free_slot_bits: u128,
+    pub is_bid_bits: u128,
+    pub orders: [u128; 128],
+    pub client_order_ids: [u64; 128],
+    pub referrer_rebates_accrued: u64,
+
+    _tail_pad: [u8; 7],
+}
+
+unsafe impl Zeroable for SerumOpenOrders {}
+unsafe impl Pod for SerumOpenOrders {}
+
+impl SerumOpenOrders {
+    pub fn deserialize(buf: &[u8]) -> Result<&Self, PodCastError> {
+        const FLAGS: u64 = (AccountFlag::Initialized as u64)
+            | (AccountFlag::OpenOrders as u64);
+
+        let r: &Self = bytemuck::try_from_bytes(buf)?;
+
+        if r._head_pad[..] != *"serum".as_bytes()
+            || r._tail_pad[..] != *"padding".as_bytes()
+            || r.account_flags & FLAGS != FLAGS
+        {
+            panic!("Invalid buffer for serum open orders");
+        }
+
+        Ok(r)
+    }
+
+    /// Iterator over the `(order_id, client_order_id, is_bid)` of the
+    /// occupied order slots.
+    pub fn iter_orders(&self) -> impl Iterator<Item = (u128, u64, bool)> {
+        let (free, is_bid) = (self.free_slot_bits, self.is_bid_bits);
+        let (orders, client_ids) = (self.orders, self.client_order_ids);
+
+        (0..128usize)
+            .filter(move |i| free & (1u128 << i) == 0)
+            .map(move |i| {
+                (orders[i], client_ids[i], is_bid & (1u128 << i) != 0)
+            })
+    }
+}
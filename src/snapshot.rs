@@ -0,0 +1,209 @@
+//! Owned copy of the accounts of one margin, for offline analysis.
+//!
+//! ```ignore
+//! let accounts = rpc.get_multiple_accounts(&keys)?;
+//! let snapshot = ZoSnapshot::new(
+//!     ZO_STATE_ID,
+//!     margin_key,
+//!     keys.into_iter().zip(accounts.into_iter().map(|a| a.data)),
+//! )?;
+//! let health = snapshot.health(FractionType::Maintenance);
+//! ```
+
+use crate::{
+    dex::{Side, Slab, ZoDexMarket},
+    funding::unsettled_funding,
+    health::{collateral_balance, MarginHealth},
+    special_orders_address, Cache, Control, FractionType, Margin,
+    OpenOrdersInfo, SpecialOrders, State,
+};
+use anchor_lang::{prelude::Pubkey, Discriminator};
+use bytemuck::Pod;
+use fixed::types::I80F48;
+use std::{collections::BTreeMap, mem::size_of};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    MissingAccount(Pubkey),
+    /// Wrong discriminator or size, or a book that does not pass
+    /// `Slab::is_valid`.
+    InvalidAccount(Pubkey),
+    /// `Control.authority` differs from `Margin.authority`.
+    AuthorityMismatch,
+    /// A dex market account does not match `State.perp_markets[index]`.
+    MarketMismatch {
+        index: usize,
+        key: Pubkey,
+    },
+    /// `SpecialOrders.authority` differs from `Margin.authority`.
+    SpecialOrdersMismatch,
+}
+
+/// Anchor account at `data`, checking the discriminator and size.
+pub fn load_account<T: Discriminator + Pod>(data: &[u8]) -> Option<&T> {
+    if data.get(..8)? != T::discriminator() {
+        return None;
+    }
+    bytemuck::try_from_bytes(data.get(8..8 + size_of::<T>())?).ok()
+}
+
+/// Account data keyed by pubkey, checked to belong together on creation.
+/// `State.cache` and `Margin.control` must be present, and can be joined
+/// by the margin's `SpecialOrders` and the dex markets and books of any
+/// market.
+#[derive(Clone, Debug)]
+pub struct ZoSnapshot {
+    pub state_key: Pubkey,
+    pub margin_key: Pubkey,
+    accounts: BTreeMap<Pubkey, Vec<u8>>,
+}
+
+impl ZoSnapshot {
+    pub fn new(
+        state_key: Pubkey,
+        margin_key: Pubkey,
+        accounts: impl IntoIterator<Item = (Pubkey, Vec<u8>)>,
+    ) -> Result<Self, SnapshotError> {
+        let r = Self {
+            state_key,
+            margin_key,
+            accounts: accounts.into_iter().collect(),
+        };
+        r.validate()?;
+        Ok(r)
+    }
+
+    fn load<T: Discriminator + Pod>(
+        &self,
+        key: &Pubkey,
+    ) -> Result<&T, SnapshotError> {
+        let data = self
+            .accounts
+            .get(key)
+            .ok_or(SnapshotError::MissingAccount(*key))?;
+        load_account(data).ok_or(SnapshotError::InvalidAccount(*key))
+    }
+
+    fn validate(&self) -> Result<(), SnapshotError> {
+        let state: &State = self.load(&self.state_key)?;
+        let margin: &Margin = self.load(&self.margin_key)?;
+        let _: &Cache = self.load(&state.cache)?;
+        let control: &Control = self.load(&margin.control)?;
+
+        if control.authority != margin.authority {
+            return Err(SnapshotError::AuthorityMismatch);
+        }
+
+        let key = self.special_orders_key();
+        if self.accounts.contains_key(&key) {
+            let so: &SpecialOrders = self.load(&key)?;
+            if so.authority != margin.authority {
+                return Err(SnapshotError::SpecialOrdersMismatch);
+            }
+        }
+
+        for index in 0..state.total_markets as usize {
+            let key = state.perp_markets[index].dex_market;
+            let data = match self.accounts.get(&key) {
+                Some(x) => x,
+                None => continue,
+            };
+            let m: &ZoDexMarket = bytemuck::try_from_bytes(data)
+                .map_err(|_| SnapshotError::InvalidAccount(key))?;
+            if m.own_address != key {
+                return Err(SnapshotError::MarketMismatch { index, key });
+            }
+
+            for book in [m.bids, m.asks] {
+                match self.accounts.get(&book) {
+                    Some(x) if !Slab::is_valid(x) => {
+                        return Err(SnapshotError::InvalidAccount(book));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Raw data of any account in the snapshot.
+    pub fn account(&self, key: &Pubkey) -> Option<&[u8]> {
+        self.accounts.get(key).map(Vec::as_slice)
+    }
+
+    pub fn state(&self) -> &State {
+        self.load(&self.state_key).unwrap()
+    }
+
+    pub fn cache(&self) -> &Cache {
+        self.load(&self.state().cache).unwrap()
+    }
+
+    pub fn margin(&self) -> &Margin {
+        self.load(&self.margin_key).unwrap()
+    }
+
+    pub fn control(&self) -> &Control {
+        self.load(&self.margin().control).unwrap()
+    }
+
+    pub fn special_orders_key(&self) -> Pubkey {
+        let authority = self.margin().authority;
+        special_orders_address(&authority, &self.state_key).0
+    }
+
+    pub fn special_orders(&self) -> Option<&SpecialOrders> {
+        self.load(&self.special_orders_key()).ok()
+    }
+
+    /// Index into `State.perp_markets` of the market with the symbol.
+    pub fn market_index(&self, symbol: &str) -> Option<usize> {
+        let state = self.state();
+        state.perp_markets[..state.total_markets as usize]
+            .iter()
+            .position(|m| String::from(m.symbol) == symbol)
+    }
+
+    pub fn dex_market(&self, index: usize) -> Option<&ZoDexMarket> {
+        let key = self.state().perp_markets.get(index)?.dex_market;
+        bytemuck::try_from_bytes(self.account(&key)?).ok()
+    }
+
+    pub fn book(&self, index: usize, side: Side) -> Option<Slab<'_>> {
+        let m = self.dex_market(index)?;
+        let key = match side {
+            Side::Bid => m.bids,
+            Side::Ask => m.asks,
+        };
+        let data = self.account(&key)?;
+        match Slab::is_valid(data) {
+            true => Slab::deserialize(data).ok(),
+            false => None,
+        }
+    }
+
+    pub fn health(&self, ty: FractionType) -> MarginHealth {
+        MarginHealth::new(
+            self.state(),
+            self.cache(),
+            self.margin(),
+            self.control(),
+            ty,
+        )
+    }
+
+    /// Balance of collateral `i` in smol, with interest.
+    pub fn balance(&self, i: usize) -> I80F48 {
+        collateral_balance(self.cache(), self.margin(), i)
+    }
+
+    pub fn position(&self, index: usize) -> &OpenOrdersInfo {
+        &self.control().open_orders_agg[index]
+    }
+
+    /// See `funding::unsettled_funding`.
+    pub fn unsettled_funding(&self, index: usize) -> I80F48 {
+        unsettled_funding(self.cache(), self.position(index), index)
+    }
+}
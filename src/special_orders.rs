@@ -0,0 +1,278 @@
+//! Helpers for executors of special orders.
+
+use crate::{
+    dex::ZoDexMarket, margin_address, open_orders_address,
+    state_signer_address, Cache, PerpMarketInfo, SpecialOrderType,
+    SpecialOrders, SpecialOrdersInfo, State, MAX_SPECIAL_ORDERS, ZO_DEX_PID,
+};
+use anchor_lang::{
+    prelude::{Pubkey, Rent},
+    solana_program::sysvar::SysvarId,
+    InstructionData, ToAccountMetas,
+};
+use fixed::types::I80F48;
+use solana_program::instruction::Instruction;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PriceSource {
+    /// `Cache.marks`
+    Mark,
+    /// `Cache.oracles`, by the market's `oracle_symbol`.
+    Oracle,
+}
+
+/// Current price of the market at `index` in smol per big, the unit of
+/// `SpecialOrdersInfo.trigger_price`.
+pub fn market_price(
+    state: &State,
+    cache: &Cache,
+    index: usize,
+    source: PriceSource,
+) -> Option<u64> {
+    let info = &state.perp_markets[index];
+    let (price, decimals) = match source {
+        PriceSource::Mark => {
+            (I80F48::from(cache.marks[index].price), info.asset_decimals)
+        }
+        PriceSource::Oracle => {
+            let o = cache.get_oracle(&info.oracle_symbol)?;
+            (I80F48::from(o.price), o.base_decimals)
+        }
+    };
+
+    price
+        .checked_mul(I80F48::from_num(10u64.checked_pow(decimals as u32)?))?
+        .checked_to_num()
+}
+
+#[derive(Copy, Clone)]
+pub struct TriggeredOrder {
+    pub special_orders: Pubkey,
+    pub authority: Pubkey,
+    /// Index into `State.perp_markets`.
+    pub market_index: usize,
+    pub order: SpecialOrdersInfo,
+    /// Price the order triggered at, in smol per big.
+    pub price: u64,
+    /// Distance of the price past the trigger, in bps of the trigger.
+    pub urgency_bps: u64,
+}
+
+impl TriggeredOrder {
+    /// `execute_special_order` instruction. The payer receives the
+    /// order's `fee`. `control` is the authority's `Margin.control`.
+    pub fn instruction(
+        &self,
+        payer: &Pubkey,
+        state_key: &Pubkey,
+        state: &State,
+        control: &Pubkey,
+        market: &ZoDexMarket,
+    ) -> Instruction {
+        let open_orders = open_orders_address(control, &market.own_address).0;
+
+        Instruction {
+            program_id: crate::ID,
+            accounts: crate::accounts::ExecuteSpecialOrder {
+                state: *state_key,
+                state_signer: state_signer_address(state_key).0,
+                cache: state.cache,
+                payer: *payer,
+                authority: self.authority,
+                margin: margin_address(&self.authority, state_key).0,
+                control: *control,
+                special_orders: self.special_orders,
+                open_orders,
+                dex_market: market.own_address,
+                req_q: market.req_q,
+                event_q: market.event_q,
+                market_bids: market.bids,
+                market_asks: market.asks,
+                dex_program: ZO_DEX_PID,
+                rent: Rent::id(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ExecuteSpecialOrder { id: self.order.id }
+                .data(),
+        }
+    }
+}
+
+/// Orders of `accounts` that trigger at the current price, most urgent
+/// first, then by highest fee. `source` picks the price of each market.
+pub fn triggered_orders(
+    state: &State,
+    cache: &Cache,
+    accounts: &[(Pubkey, &SpecialOrders)],
+    source: impl Fn(&PerpMarketInfo) -> PriceSource,
+) -> Vec<TriggeredOrder> {
+    let markets = &state.perp_markets[..state.total_markets as usize];
+    let prices: Vec<Option<u64>> = markets
+        .iter()
+        .enumerate()
+        .map(|(i, m)| market_price(state, cache, i, source(m)))
+        .collect();
+
+    let mut r: Vec<TriggeredOrder> = Vec::new();
+
+    for (key, acc) in accounts {
+        for order in acc.iter() {
+            let market = order.market;
+            let market_index =
+                match markets.iter().position(|m| m.dex_market == market) {
+                    Some(i) => i,
+                    None => continue,
+                };
+            let price = match prices[market_index] {
+                Some(p) if p > 0 => p,
+                _ => continue,
+            };
+
+            if !order.is_triggered(price) {
+                continue;
+            }
+
+            let trigger = order.trigger_price.max(1);
+            r.push(TriggeredOrder {
+                special_orders: *key,
+                authority: acc.authority,
+                market_index,
+                order: *order,
+                price,
+                urgency_bps: (price.abs_diff(trigger) as u128 * 10_000
+                    / trigger as u128) as u64,
+            });
+        }
+    }
+
+    r.sort_by_key(|x| {
+        (
+            std::cmp::Reverse(x.urgency_bps),
+            std::cmp::Reverse(x.order.fee),
+        )
+    });
+    r
+}
+
+/// Arguments of `place_special_order`.
+#[derive(Copy, Clone, Debug)]
+pub struct SpecialOrderParams {
+    pub is_long: bool,
+    pub ty: SpecialOrderType,
+    /// In smol per big.
+    pub trigger_price: u64,
+    /// In smol per big.
+    pub limit_price: u64,
+    /// In lots.
+    pub size: u64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SpecialOrderError {
+    ZeroSize,
+    /// The trigger or limit price is less than one price lot.
+    PriceBelowLot,
+    MissingLimitPrice,
+    /// The order is already triggered at the current mark price.
+    TriggersImmediately,
+    /// All `MAX_SPECIAL_ORDERS` entries are in use.
+    Full,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SpecialOrderWarning {
+    /// Market types do not use `limit_price`.
+    LimitPriceOnMarketOrder,
+    /// The trigger price is not a whole number of price lots.
+    TriggerPriceNotOnLot,
+    /// The limit price is not a whole number of price lots.
+    LimitPriceNotOnLot,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpecialOrderCheck {
+    /// `id` the order will be assigned.
+    pub next_id: u16,
+    pub warnings: Vec<SpecialOrderWarning>,
+}
+
+/// Checks `params` before signing `place_special_order`. `mark_price` is
+/// in smol per big, see `market_price`.
+pub fn validate_special_order(
+    params: &SpecialOrderParams,
+    market: &ZoDexMarket,
+    orders: &SpecialOrders,
+    mark_price: u64,
+) -> Result<SpecialOrderCheck, SpecialOrderError> {
+    use SpecialOrderType::*;
+
+    let mut warnings: Vec<SpecialOrderWarning> = Vec::new();
+
+    if params.size == 0 {
+        return Err(SpecialOrderError::ZeroSize);
+    }
+
+    // Smol per big of a single price lot is `lot_num / lot_den`.
+    let lot_num =
+        market.pc_lot_size as u128 * 10u128.pow(market.coin_decimals as u32);
+    let lot_den = market.coin_lot_size as u128;
+    let check_price = |p: u64, off_lot| {
+        let n = p as u128 * lot_den;
+        if n < lot_num {
+            Err(SpecialOrderError::PriceBelowLot)
+        } else {
+            Ok((n % lot_num != 0).then_some(off_lot))
+        }
+    };
+
+    warnings.extend(check_price(
+        params.trigger_price,
+        SpecialOrderWarning::TriggerPriceNotOnLot,
+    )?);
+
+    match params.ty {
+        TakeProfitLimit | StopLossLimit => {
+            if params.limit_price == 0 {
+                return Err(SpecialOrderError::MissingLimitPrice);
+            }
+            warnings.extend(check_price(
+                params.limit_price,
+                SpecialOrderWarning::LimitPriceNotOnLot,
+            )?);
+        }
+        TakeProfitMarket | StopLossMarket => {
+            if params.limit_price != 0 {
+                warnings.push(SpecialOrderWarning::LimitPriceOnMarketOrder);
+            }
+        }
+    }
+
+    let info = SpecialOrdersInfo {
+        id: 0,
+        market: market.own_address,
+        ty: params.ty,
+        is_long: params.is_long,
+        trigger_price: params.trigger_price,
+        limit_price: params.limit_price,
+        size: params.size,
+        fee: 0,
+    };
+    if info.is_triggered(mark_price) {
+        return Err(SpecialOrderError::TriggersImmediately);
+    }
+
+    if orders.iter().count() >= MAX_SPECIAL_ORDERS {
+        return Err(SpecialOrderError::Full);
+    }
+
+    // Ids increase from `prev_id`, skipping 0 which marks empty entries.
+    let mut next_id = orders.prev_id;
+    loop {
+        next_id = next_id.wrapping_add(1);
+        if next_id != 0 && orders.iter().all(|x| x.id != next_id) {
+            break;
+        }
+    }
+
+    Ok(SpecialOrderCheck { next_id, warnings })
+}
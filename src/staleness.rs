@@ -0,0 +1,169 @@
+//! Freshness of the values keepers maintain in `Cache` and the dex
+//! markets.
+
+use crate::{dex::ZoDexMarket, Cache, State, Symbol};
+use anchor_lang::prelude::Pubkey;
+
+/// Maximum ages in seconds before a value is reported as stale.
+#[derive(Copy, Clone, Debug)]
+pub struct StalenessThresholds {
+    /// `OracleCache.last_updated`
+    pub oracle: u64,
+    /// `BorrowCache.last_updated`
+    pub borrow: u64,
+    /// `TwapInfo.last_sample_start_time`
+    pub mark: u64,
+    /// `ZoDexMarket.last_updated`
+    pub funding: u64,
+}
+
+impl Default for StalenessThresholds {
+    fn default() -> Self {
+        Self {
+            oracle: 60,
+            borrow: 300,
+            mark: 300,
+            funding: 3600,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct StaleEntry {
+    /// Index into `Cache.oracles` or `State.collaterals`.
+    pub index: usize,
+    pub symbol: Symbol,
+    pub last_updated: u64,
+    pub age: u64,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct StaleMarket {
+    /// Index into `State.perp_markets`.
+    pub index: usize,
+    pub symbol: Symbol,
+    pub oracle_symbol: Symbol,
+    pub dex_market: Pubkey,
+    pub last_updated: u64,
+    pub age: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StalenessReport {
+    pub oracles: Vec<StaleEntry>,
+    pub borrows: Vec<StaleEntry>,
+    /// Markets whose mark twap has not been sampled recently.
+    pub marks: Vec<StaleMarket>,
+    pub funding: Vec<StaleMarket>,
+}
+
+impl StalenessReport {
+    /// Compares every value against `now`, a unix timestamp. Funding is
+    /// only checked for the given `markets`.
+    pub fn new(
+        state: &State,
+        cache: &Cache,
+        markets: &[ZoDexMarket],
+        now: u64,
+        thresholds: &StalenessThresholds,
+    ) -> Self {
+        let mut r = Self::default();
+        let age = |t: u64| now.saturating_sub(t);
+
+        for (index, o) in cache.oracles.iter().enumerate() {
+            let last_updated = o.last_updated;
+            if !o.symbol.is_nil() && age(last_updated) > thresholds.oracle {
+                r.oracles.push(StaleEntry {
+                    index,
+                    symbol: o.symbol,
+                    last_updated,
+                    age: age(last_updated),
+                });
+            }
+        }
+
+        for index in 0..state.total_collaterals as usize {
+            let last_updated = cache.borrow_cache[index].last_updated;
+            if age(last_updated) > thresholds.borrow {
+                r.borrows.push(StaleEntry {
+                    index,
+                    symbol: state.collaterals[index].oracle_symbol,
+                    last_updated,
+                    age: age(last_updated),
+                });
+            }
+        }
+
+        for index in 0..state.total_markets as usize {
+            let info = &state.perp_markets[index];
+            let stale = |last_updated: u64| StaleMarket {
+                index,
+                symbol: info.symbol,
+                oracle_symbol: info.oracle_symbol,
+                dex_market: info.dex_market,
+                last_updated,
+                age: age(last_updated),
+            };
+
+            let sampled = cache.marks[index].twap.last_sample_start_time;
+            if age(sampled) > thresholds.mark {
+                r.marks.push(stale(sampled));
+            }
+
+            let funded = markets
+                .iter()
+                .find(|m| m.own_address == info.dex_market)
+                .map(|m| m.last_updated);
+            if let Some(funded) = funded {
+                if age(funded) > thresholds.funding {
+                    r.funding.push(stale(funded));
+                }
+            }
+        }
+
+        r
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        self.oracles.is_empty()
+            && self.borrows.is_empty()
+            && self.marks.is_empty()
+            && self.funding.is_empty()
+    }
+
+    /// Symbols to pass to `cache_oracle`, which also samples the mark
+    /// twap of the markets using them.
+    pub fn cache_oracle_symbols(&self) -> Vec<String> {
+        let mut r: Vec<String> = Vec::new();
+        let stale = self.oracles.iter().map(|x| x.symbol);
+        let marks = self.marks.iter().map(|x| x.oracle_symbol);
+
+        for s in stale.chain(marks).map(String::from) {
+            if !r.contains(&s) {
+                r.push(s);
+            }
+        }
+
+        r
+    }
+
+    /// Half-open `start..end` collateral ranges to pass to
+    /// `cache_interest_rates`.
+    pub fn cache_interest_rates_ranges(&self) -> Vec<(u8, u8)> {
+        let mut r: Vec<(u8, u8)> = Vec::new();
+
+        for i in self.borrows.iter().map(|x| x.index as u8) {
+            match r.last_mut() {
+                Some((_, end)) if *end == i => *end += 1,
+                _ => r.push((i, i + 1)),
+            }
+        }
+
+        r
+    }
+
+    /// Dex markets to pass to `update_perp_funding`.
+    pub fn update_perp_funding_markets(&self) -> Vec<Pubkey> {
+        self.funding.iter().map(|x| x.dex_market).collect()
+    }
+}
@@ -0,0 +1,159 @@
+//! Quotes for the `swap` instruction against a serum order book.
+
+use crate::{dex::Slab, serum::SerumMarket, State};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SwapQuoteError {
+    ZeroAmount,
+    /// The market's coin or pc mint is not a collateral, or the pc mint
+    /// is not the collateral at index 0.
+    UnknownCollateral,
+    NotSwappable,
+    /// The book is not the side the swap takes from.
+    WrongBookSide,
+    /// The book cannot absorb `amount`, or `amount` buys less than a lot.
+    InsufficientLiquidity,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SwapParams {
+    /// Swaps quote for asset when true, asset for quote otherwise.
+    pub buy: bool,
+    /// Smol amount to swap *from*.
+    pub amount: u64,
+    /// Taker fee charged on the quote side, in bps.
+    pub fee_bps: u64,
+    /// Tolerated shortfall of the received amount, in bps.
+    pub slippage_bps: u64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Smol amount actually taken from the book, including the fee.
+    pub amount_in: u64,
+    /// Smol amount received, after fees.
+    pub amount_out: u64,
+    /// Smol quote paid in fees.
+    pub fee: u64,
+    /// Distance between the average fill price and the best price, in bps.
+    pub price_impact_bps: u64,
+    /// Value to pass as `min_rate`, i.e. the minimum smol received per big
+    /// token taken after slippage. Based on `amount_in`, so that lot
+    /// rounding does not loosen it.
+    pub min_rate: u64,
+}
+
+/// Simulates an IOC swap of `params.amount` against `book`, which must be
+/// the asks when buying and the bids when selling. Fails rather than quote
+/// a partial fill.
+pub fn quote_swap(
+    state: &State,
+    market: &SerumMarket,
+    book: &Slab,
+    params: SwapParams,
+) -> Result<SwapQuote, SwapQuoteError> {
+    if params.amount == 0 {
+        return Err(SwapQuoteError::ZeroAmount);
+    }
+
+    let cols = &state.collaterals[..state.total_collaterals as usize];
+    let quote = cols
+        .first()
+        .filter(|c| c.mint == market.pc_mint)
+        .ok_or(SwapQuoteError::UnknownCollateral)?;
+    let asset = cols
+        .iter()
+        .find(|c| c.mint == market.coin_mint)
+        .ok_or(SwapQuoteError::UnknownCollateral)?;
+
+    if !asset.is_swappable {
+        return Err(SwapQuoteError::NotSwappable);
+    }
+    if book.is_bids() == params.buy {
+        return Err(SwapQuoteError::WrongBookSide);
+    }
+
+    let pc_lot_size = market.pc_lot_size as u128;
+    let coin_lot_size = market.coin_lot_size as u128;
+    let fee_bps = params.fee_bps as u128;
+
+    let best = book
+        .get_best()
+        .ok_or(SwapQuoteError::InsufficientLiquidity)?
+        .price()
+        .get();
+
+    // Lots of coin filled, and smol quote exchanged for them before fees.
+    let mut lots = 0u128;
+    let mut notional = 0u128;
+
+    if params.buy {
+        let mut budget = params.amount as u128 * 10_000 / (10_000 + fee_bps);
+        let mut filled = false;
+        for leaf in book.iter_best() {
+            let lot_cost = leaf.price().get() as u128 * pc_lot_size;
+            let take = (leaf.quantity as u128).min(budget / lot_cost);
+
+            lots += take;
+            notional += take * lot_cost;
+            budget -= take * lot_cost;
+
+            // Later levels cost at least as much, so what is left is lot
+            // rounding.
+            if budget < lot_cost {
+                filled = true;
+                break;
+            }
+        }
+        if !filled {
+            return Err(SwapQuoteError::InsufficientLiquidity);
+        }
+    } else {
+        let mut budget = params.amount as u128 / coin_lot_size;
+        for leaf in book.iter_best() {
+            let take = (leaf.quantity as u128).min(budget);
+
+            lots += take;
+            notional += take * leaf.price().get() as u128 * pc_lot_size;
+            budget -= take;
+
+            if budget == 0 {
+                break;
+            }
+        }
+        if budget > 0 {
+            return Err(SwapQuoteError::InsufficientLiquidity);
+        }
+    }
+
+    if lots == 0 {
+        return Err(SwapQuoteError::InsufficientLiquidity);
+    }
+
+    let fee = (notional * fee_bps + 9_999) / 10_000;
+    let (amount_in, amount_out, from_decimals) = match params.buy {
+        true => (notional + fee, lots * coin_lot_size, quote.decimals),
+        false => (
+            lots * coin_lot_size,
+            notional.saturating_sub(fee),
+            asset.decimals,
+        ),
+    };
+
+    let avg = notional as f64 / (lots * pc_lot_size) as f64;
+    let price_impact_bps =
+        ((avg - best as f64).abs() / best as f64 * 10_000f64).round() as u64;
+
+    let min_out = amount_out
+        * 10_000u128.saturating_sub(params.slippage_bps as u128)
+        / 10_000;
+    let min_rate = min_out * 10u128.pow(from_decimals as u32) / amount_in;
+
+    Ok(SwapQuote {
+        amount_in: amount_in as u64,
+        amount_out: amount_out as u64,
+        fee: fee as u64,
+        price_impact_bps,
+        min_rate: min_rate as u64,
+    })
+}
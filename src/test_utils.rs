@@ -0,0 +1,567 @@
+//! In-memory builders for zo accounts, enabled with the `test-utils`
+//! feature.
+//!
+//! ```ignore
+//! use zo_abi::test_utils::*;
+//!
+//! let state = StateBuilder::new()
+//!     .add_collateral(usdc_mint, "USDC", 6, 1000)
+//!     .add_perp_market("BTC-PERP", "BTC", PerpType::Future, 6, 100, 10, btc_market)
+//!     .build();
+//! let asks = SlabBuilder::new(Side::Ask)
+//!     .add_order(20_000, 1, 10, control, 0)
+//!     .build_bytes();
+//! ```
+
+use crate::{
+    dex::{AccountFlag, Event, EventFlag, LeafNode, OrderId, Side},
+    Cache, Control, Margin, OracleSource, OracleType, PerpType, State, Symbol,
+    WrappedI80F48, MAX_COLLATERALS, MAX_MARKETS, MAX_ORACLE_SOURCES,
+};
+use anchor_lang::{prelude::Pubkey, Discriminator};
+use bytemuck::{Pod, Zeroable};
+use fixed::types::I80F48;
+
+/// Prefixes the account with its Anchor discriminator, as stored on chain.
+pub fn to_account_bytes<T: Discriminator + Pod>(account: &T) -> Vec<u8> {
+    let mut buf = T::discriminator().to_vec();
+    buf.extend_from_slice(bytemuck::bytes_of(account));
+    buf
+}
+
+fn to_symbol(s: &str) -> Symbol {
+    Symbol::try_from(s).expect("symbol longer than 24 bytes")
+}
+
+pub struct StateBuilder {
+    state: State,
+}
+
+impl Default for StateBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateBuilder {
+    pub fn new() -> Self {
+        Self {
+            state: State::zeroed(),
+        }
+    }
+
+    pub fn admin(mut self, admin: Pubkey) -> Self {
+        self.state.admin = admin;
+        self
+    }
+
+    pub fn cache(mut self, cache: Pubkey) -> Self {
+        self.state.cache = cache;
+        self
+    }
+
+    /// `weight` is in permil. The collateral at index 0 is the quote
+    /// currency, normally USDC.
+    pub fn add_collateral(
+        mut self,
+        mint: Pubkey,
+        oracle_symbol: &str,
+        decimals: u8,
+        weight: u16,
+    ) -> Self {
+        let i = self.state.total_collaterals as usize;
+        assert!(i < MAX_COLLATERALS, "too many collaterals");
+
+        let col = &mut self.state.collaterals[i];
+        col.mint = mint;
+        col.oracle_symbol = to_symbol(oracle_symbol);
+        col.decimals = decimals;
+        col.weight = weight;
+
+        self.state.total_collaterals += 1;
+        self
+    }
+
+    /// Marks the collateral with the given mint as swappable through
+    /// `serum_open_orders`.
+    pub fn swappable(
+        mut self,
+        mint: Pubkey,
+        serum_open_orders: Pubkey,
+    ) -> Self {
+        let col = self
+            .state
+            .collaterals
+            .iter_mut()
+            .find(|c| c.mint == mint)
+            .expect("unknown collateral mint");
+        col.is_swappable = true;
+        col.serum_open_orders = serum_open_orders;
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_perp_market(
+        mut self,
+        symbol: &str,
+        oracle_symbol: &str,
+        perp_type: PerpType,
+        asset_decimals: u8,
+        asset_lot_size: u64,
+        quote_lot_size: u64,
+        dex_market: Pubkey,
+    ) -> Self {
+        let i = self.state.total_markets as usize;
+        assert!(i < MAX_MARKETS, "too many markets");
+
+        let m = &mut self.state.perp_markets[i];
+        m.symbol = to_symbol(symbol);
+        m.oracle_symbol = to_symbol(oracle_symbol);
+        m.perp_type = perp_type;
+        m.asset_decimals = asset_decimals;
+        m.asset_lot_size = asset_lot_size;
+        m.quote_lot_size = quote_lot_size;
+        m.base_imf = 100;
+        m.liq_fee = 20;
+        m.dex_market = dex_market;
+
+        self.state.total_markets += 1;
+        self
+    }
+
+    /// Sets `strike` and `base_imf` of the most recently added market.
+    pub fn market_params(mut self, strike: u64, base_imf: u16) -> Self {
+        let i = (self.state.total_markets as usize)
+            .checked_sub(1)
+            .expect("no market added");
+        self.state.perp_markets[i].strike = strike;
+        self.state.perp_markets[i].base_imf = base_imf;
+        self
+    }
+
+    pub fn build(self) -> State {
+        self.state
+    }
+
+    pub fn build_bytes(self) -> Vec<u8> {
+        to_account_bytes(&self.state)
+    }
+}
+
+pub struct CacheBuilder {
+    cache: Cache,
+    time: u64,
+}
+
+impl Default for CacheBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheBuilder {
+    /// Starts with every borrow multiplier set to one, so that margin
+    /// collateral maps one to one onto balances.
+    pub fn new() -> Self {
+        let mut cache = Cache::zeroed();
+        for b in cache.borrow_cache.iter_mut() {
+            b.supply_multiplier = I80F48::ONE.into();
+            b.borrow_multiplier = I80F48::ONE.into();
+        }
+        Self { cache, time: 0 }
+    }
+
+    /// Timestamp written to `last_updated` by subsequent setters.
+    pub fn updated_at(mut self, time: u64) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// `price` is in smol quote per smol asset. Oracles are kept sorted
+    /// by symbol so that `Cache::get_oracle` finds them.
+    pub fn set_oracle_price(
+        mut self,
+        oracle_symbol: &str,
+        price: I80F48,
+        base_decimals: u8,
+        quote_decimals: u8,
+    ) -> Self {
+        let s = to_symbol(oracle_symbol);
+        let i = match self.cache.oracles.iter().position(|o| o.symbol == s) {
+            Some(i) => i,
+            None => {
+                // Nil symbols sort first, so the first slot is free.
+                assert!(
+                    self.cache.oracles[0].symbol.is_nil(),
+                    "too many oracles"
+                );
+                0
+            }
+        };
+
+        let o = &mut self.cache.oracles[i];
+        o.symbol = s;
+        o.price = price.into();
+        o.twap = price.into();
+        o.base_decimals = base_decimals;
+        o.quote_decimals = quote_decimals;
+        o.last_updated = self.time;
+
+        self.cache.oracles.sort_by_key(|o| o.symbol);
+        self
+    }
+
+    /// Sources of an oracle set by `set_oracle_price`. Unused slots are
+    /// `OracleType::Nil`.
+    pub fn set_oracle_sources(
+        mut self,
+        oracle_symbol: &str,
+        sources: &[(OracleType, Pubkey)],
+    ) -> Self {
+        assert!(sources.len() <= MAX_ORACLE_SOURCES, "too many sources");
+        let s = to_symbol(oracle_symbol);
+        let o = self
+            .cache
+            .oracles
+            .iter_mut()
+            .find(|o| o.symbol == s)
+            .expect("oracle not set");
+
+        o.sources = [OracleSource {
+            ty: OracleType::Nil,
+            key: Pubkey::default(),
+        }; MAX_ORACLE_SOURCES];
+        for (x, (ty, key)) in o.sources.iter_mut().zip(sources) {
+            *x = OracleSource { ty: *ty, key: *key };
+        }
+        self
+    }
+
+    /// `price` is in smol quote per smol asset.
+    pub fn set_mark_price(
+        mut self,
+        market_index: usize,
+        price: I80F48,
+    ) -> Self {
+        let m = &mut self.cache.marks[market_index];
+        m.price = price.into();
+        m.twap.cumul_avg = price.into();
+        m.twap.open = price.into();
+        m.twap.high = price.into();
+        m.twap.low = price.into();
+        m.twap.close = price.into();
+        m.twap.last_sample_start_time = self.time;
+        self
+    }
+
+    pub fn set_funding(mut self, market_index: usize, funding: i128) -> Self {
+        let mut f = self.cache.funding_cache;
+        f[market_index] = funding;
+        self.cache.funding_cache = f;
+        self
+    }
+
+    /// `supply` and `borrows` are in smol.
+    pub fn set_borrows(
+        mut self,
+        col_index: usize,
+        supply: I80F48,
+        borrows: I80F48,
+    ) -> Self {
+        let b = &mut self.cache.borrow_cache[col_index];
+        b.supply = supply.into();
+        b.borrows = borrows.into();
+        b.last_updated = self.time;
+        self
+    }
+
+    pub fn build(self) -> Cache {
+        self.cache
+    }
+
+    pub fn build_bytes(self) -> Vec<u8> {
+        to_account_bytes(&self.cache)
+    }
+}
+
+pub struct MarginBuilder {
+    margin: Margin,
+}
+
+impl MarginBuilder {
+    pub fn new(authority: Pubkey, control: Pubkey) -> Self {
+        let mut margin = Margin::zeroed();
+        margin.authority = authority;
+        margin.control = control;
+        Self { margin }
+    }
+
+    pub fn nonce(mut self, nonce: u8) -> Self {
+        self.margin.nonce = nonce;
+        self
+    }
+
+    /// `amount` is in smol, before being divided by the borrow cache
+    /// multiplier.
+    pub fn set_collateral(mut self, col_index: usize, amount: I80F48) -> Self {
+        self.margin.collateral[col_index] = WrappedI80F48::from(amount);
+        self
+    }
+
+    pub fn build(self) -> Margin {
+        self.margin
+    }
+
+    pub fn build_bytes(self) -> Vec<u8> {
+        to_account_bytes(&self.margin)
+    }
+}
+
+pub struct ControlBuilder {
+    control: Control,
+}
+
+impl ControlBuilder {
+    pub fn new(authority: Pubkey) -> Self {
+        let mut control = Control::zeroed();
+        control.authority = authority;
+        Self { control }
+    }
+
+    /// `pos_size` is in smol asset, `native_pc_total` in smol quote.
+    pub fn set_position(
+        mut self,
+        market_index: usize,
+        open_orders: Pubkey,
+        pos_size: i64,
+        native_pc_total: i64,
+    ) -> Self {
+        let oo = &mut self.control.open_orders_agg[market_index];
+        oo.key = open_orders;
+        oo.pos_size = pos_size;
+        oo.native_pc_total = native_pc_total;
+        self
+    }
+
+    /// Sets the resting order totals of a market, in smol asset.
+    pub fn set_orders(
+        mut self,
+        market_index: usize,
+        coin_on_bids: u64,
+        coin_on_asks: u64,
+        order_count: u8,
+    ) -> Self {
+        let oo = &mut self.control.open_orders_agg[market_index];
+        oo.coin_on_bids = coin_on_bids;
+        oo.coin_on_asks = coin_on_asks;
+        oo.order_count = order_count;
+        self
+    }
+
+    pub fn build(self) -> Control {
+        self.control
+    }
+
+    pub fn build_bytes(self) -> Vec<u8> {
+        to_account_bytes(&self.control)
+    }
+}
+
+/// One side of a zo dex or serum book, laid out as the crit-bit tree
+/// `dex::Slab` reads.
+pub struct SlabBuilder {
+    side: Side,
+    leaves: Vec<LeafNode>,
+}
+
+impl SlabBuilder {
+    pub fn new(side: Side) -> Self {
+        Self {
+            side,
+            leaves: Vec::new(),
+        }
+    }
+
+    /// `price` and `quantity` are in lots. The order's key is
+    /// `OrderId::new(side, price, seq_num)`.
+    pub fn add_order(
+        mut self,
+        price: u64,
+        seq_num: u64,
+        quantity: u64,
+        control: Pubkey,
+        client_order_id: u64,
+    ) -> Self {
+        let mut leaf = LeafNode::zeroed();
+        leaf.key = OrderId::new(self.side, price, seq_num).key;
+        leaf.control = control;
+        leaf.quantity = quantity;
+        leaf.client_order_id = client_order_id;
+
+        self.leaves.push(leaf);
+        self
+    }
+
+    /// Account data with the root at node 0.
+    pub fn build_bytes(mut self) -> Vec<u8> {
+        self.leaves.sort_by_key(|x| x.key);
+        assert!(
+            self.leaves.windows(2).all(|w| { w[0].key } != { w[1].key }),
+            "duplicate order key"
+        );
+
+        let mut nodes: Vec<[u8; SLAB_NODE_SIZE]> = Vec::new();
+        if !self.leaves.is_empty() {
+            push_slab_node(&mut nodes, &self.leaves);
+        }
+
+        let side = match self.side {
+            Side::Bid => AccountFlag::Bids,
+            Side::Ask => AccountFlag::Asks,
+        };
+        let flags = AccountFlag::Initialized as u64 | side as u64;
+
+        let mut buf = b"serum".to_vec();
+        buf.extend_from_slice(&flags.to_le_bytes());
+        buf.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&[0u8; 4]);
+        // Empty free list.
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&[0u8; 4]);
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        // Root.
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&(self.leaves.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&[0u8; 4]);
+
+        for n in nodes.iter() {
+            buf.extend_from_slice(n);
+        }
+        buf.extend_from_slice(b"padding");
+        buf
+    }
+}
+
+const SLAB_NODE_SIZE: usize = 72;
+
+/// Pushes the subtree of `leaves`, sorted by key, and returns its index.
+fn push_slab_node(
+    nodes: &mut Vec<[u8; SLAB_NODE_SIZE]>,
+    leaves: &[LeafNode],
+) -> u32 {
+    let i = nodes.len();
+    nodes.push([0u8; SLAB_NODE_SIZE]);
+
+    let mut node = [0u8; SLAB_NODE_SIZE];
+    if let [leaf] = leaves {
+        node[..4].copy_from_slice(&2u32.to_le_bytes());
+        node[4..].copy_from_slice(bytemuck::bytes_of(leaf));
+    } else {
+        let (lo, hi) = (leaves[0].key, leaves[leaves.len() - 1].key);
+        let prefix_len = (lo ^ hi).leading_zeros();
+        let mask = (1u128 << 127) >> prefix_len;
+        let split = leaves.partition_point(|x| x.key & mask == 0);
+
+        let left = push_slab_node(nodes, &leaves[..split]);
+        let right = push_slab_node(nodes, &leaves[split..]);
+
+        node[..4].copy_from_slice(&1u32.to_le_bytes());
+        node[4..8].copy_from_slice(&prefix_len.to_le_bytes());
+        node[8..24].copy_from_slice(&lo.to_le_bytes());
+        node[24..28].copy_from_slice(&left.to_le_bytes());
+        node[28..32].copy_from_slice(&right.to_le_bytes());
+    }
+
+    nodes[i] = node;
+    i as u32
+}
+
+/// Event queue of a zo dex or serum market, a ring buffer of
+/// `capacity` events.
+pub struct EventQueueBuilder {
+    events: Vec<Event>,
+    head: u64,
+    count: u64,
+    seq_num: u64,
+}
+
+impl EventQueueBuilder {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: vec![Event::zeroed(); capacity],
+            head: 0,
+            count: 0,
+            seq_num: 0,
+        }
+    }
+
+    /// Appends a fill. A maker bid pays quote and releases asset, and a
+    /// maker ask the other way around, in smol.
+    pub fn push_fill(
+        self,
+        is_maker: bool,
+        is_bid: bool,
+        control: Pubkey,
+        native_qty_released: u64,
+        native_qty_paid: u64,
+    ) -> Self {
+        let mut flags = EventFlag::Fill as u8;
+        if is_maker {
+            flags |= EventFlag::Maker as u8;
+        }
+        if is_bid {
+            flags |= EventFlag::Bid as u8;
+        }
+
+        let mut e = Event::zeroed();
+        e.event_flags = flags;
+        e.control = control;
+        e.native_qty_released = native_qty_released;
+        e.native_qty_paid = native_qty_paid;
+        self.push(e)
+    }
+
+    /// Appends an out event, as for a cancelled order.
+    pub fn push_out(self, control: Pubkey) -> Self {
+        let mut e = Event::zeroed();
+        e.event_flags = EventFlag::Out as u8;
+        e.control = control;
+        self.push(e)
+    }
+
+    fn push(mut self, e: Event) -> Self {
+        let len = self.events.len() as u64;
+        assert!(self.count < len, "event queue full");
+
+        self.events[((self.head + self.count) % len) as usize] = e;
+        self.count += 1;
+        self.seq_num += 1;
+        self
+    }
+
+    /// Removes the `n` oldest events, as `consume_events` does.
+    pub fn consume(mut self, n: u64) -> Self {
+        assert!(n <= self.count, "not enough events");
+
+        self.head = (self.head + n) % self.events.len() as u64;
+        self.count -= n;
+        self
+    }
+
+    pub fn build_bytes(&self) -> Vec<u8> {
+        let flags =
+            AccountFlag::Initialized as u64 | AccountFlag::EventQueue as u64;
+
+        let mut buf = b"serum".to_vec();
+        buf.extend_from_slice(&flags.to_le_bytes());
+        buf.extend_from_slice(&self.head.to_le_bytes());
+        buf.extend_from_slice(&self.count.to_le_bytes());
+        buf.extend_from_slice(&self.seq_num.to_le_bytes());
+
+        for e in self.events.iter() {
+            buf.extend_from_slice(bytemuck::bytes_of(e));
+        }
+        buf.extend_from_slice(b"padding");
+        buf
+    }
+}
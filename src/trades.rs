@@ -0,0 +1,241 @@
+//! Trade tape and OHLCV candles from dex fills.
+//!
+//! Every match produces a fill for the maker and one for the taker, so
+//! trades are only taken from maker fills. The side of a trade is the
+//! taker's.
+//!
+//! ```ignore
+//! let mut tape = TradeTape::default();
+//! let mut candles = Candles::new(60);
+//! loop {
+//!     let buf = rpc.get_account_data(&market.event_q)?;
+//!     for t in tape.push_event_queue(&market, &buf, now())? {
+//!         candles.push(t);
+//!     }
+//! }
+//! ```
+
+use crate::{
+    dex::{Event, Side, ZoDexMarket},
+    events::EventFillLog,
+};
+use anchor_lang::prelude::Pubkey;
+use bytemuck::PodCastError;
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Trade {
+    /// Dex market.
+    pub market: Pubkey,
+    /// Big quote per big asset.
+    pub price: f64,
+    /// Big asset.
+    pub size: f64,
+    /// Side of the taker.
+    pub side: Side,
+    /// Unix timestamp.
+    pub timestamp: u64,
+}
+
+impl Trade {
+    fn new(
+        market: &ZoDexMarket,
+        maker_is_bid: bool,
+        native_coin: u64,
+        native_pc: u64,
+        timestamp: u64,
+    ) -> Option<Self> {
+        if native_coin == 0 {
+            return None;
+        }
+
+        let coin_decimals = market.coin_decimals as i32;
+        let price = native_pc as f64 / native_coin as f64
+            * 10f64.powi(coin_decimals - 6);
+
+        Some(Self {
+            market: market.own_address,
+            price,
+            size: native_coin as f64 / 10f64.powi(coin_decimals),
+            side: match maker_is_bid {
+                true => Side::Ask,
+                false => Side::Bid,
+            },
+            timestamp,
+        })
+    }
+
+    /// Trade of a maker fill in the event queue, `None` for other events.
+    pub fn from_event(
+        market: &ZoDexMarket,
+        e: &Event,
+        timestamp: u64,
+    ) -> Option<Self> {
+        if !e.is_fill() || !e.is_maker() {
+            return None;
+        }
+
+        let (paid, released) = (e.native_qty_paid, e.native_qty_released);
+        match e.is_bid() {
+            true => Self::new(market, true, released, paid, timestamp),
+            false => Self::new(market, false, paid, released, timestamp),
+        }
+    }
+
+    /// Trade of a maker `EventFillLog`, `None` for takers.
+    pub fn from_fill_log(
+        market: &ZoDexMarket,
+        log: &EventFillLog,
+        timestamp: u64,
+    ) -> Option<Self> {
+        if !log.is_maker {
+            return None;
+        }
+
+        let (paid, received) = (log.qty_paid, log.qty_received);
+        match log.is_long {
+            true => Self::new(market, true, received, paid, timestamp),
+            false => Self::new(market, false, paid, received, timestamp),
+        }
+    }
+}
+
+/// Trades read from polled event queues, skipping events already seen.
+#[derive(Clone, Debug, Default)]
+pub struct TradeTape {
+    pub trades: Vec<Trade>,
+    /// Sequence number of the next unseen event, by dex market.
+    seq_nums: BTreeMap<Pubkey, u64>,
+}
+
+impl TradeTape {
+    /// Adds the trades of events pushed to `market`'s event queue since
+    /// the last call, or all events in the queue on the first call, and
+    /// returns them. `timestamp` is the time the queue was read at.
+    pub fn push_event_queue(
+        &mut self,
+        market: &ZoDexMarket,
+        buf: &[u8],
+        timestamp: u64,
+    ) -> Result<&[Trade], PodCastError> {
+        let key = market.own_address;
+        let last = match self.seq_nums.get(&key) {
+            Some(x) => *x,
+            None => {
+                let (header, _) = Event::split(buf)?;
+                header.seq_num.wrapping_sub(header.count) % (1u64 << 32)
+            }
+        };
+
+        let (events, seq_num) = Event::deserialize_since(buf, last)?;
+        let start = self.trades.len();
+
+        self.trades.extend(
+            events.filter_map(|(_, e)| Trade::from_event(market, e, timestamp)),
+        );
+        self.seq_nums.insert(key, seq_num);
+
+        Ok(&self.trades[start..])
+    }
+
+    /// Adds the trade of a fill log, if it is the maker's.
+    pub fn push_fill_log(
+        &mut self,
+        market: &ZoDexMarket,
+        log: &EventFillLog,
+        timestamp: u64,
+    ) -> Option<&Trade> {
+        let t = Trade::from_fill_log(market, log, timestamp)?;
+        self.trades.push(t);
+        self.trades.last()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Candle {
+    /// Unix timestamp, a multiple of the interval.
+    pub start: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Big asset traded.
+    pub volume: f64,
+    /// Big quote traded.
+    pub quote_volume: f64,
+    pub trades: u32,
+}
+
+impl Candle {
+    fn new(start: u64, t: &Trade) -> Self {
+        Self {
+            start,
+            open: t.price,
+            high: t.price,
+            low: t.price,
+            close: t.price,
+            volume: t.size,
+            quote_volume: t.size * t.price,
+            trades: 1,
+        }
+    }
+}
+
+/// OHLCV candles of each market. Candles without trades are omitted.
+#[derive(Clone, Debug)]
+pub struct Candles {
+    /// Seconds per candle.
+    pub interval: u64,
+    markets: BTreeMap<Pubkey, Vec<Candle>>,
+}
+
+impl Candles {
+    pub fn new(interval: u64) -> Self {
+        assert!(interval > 0, "candle interval must be positive");
+
+        Self {
+            interval,
+            markets: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_trades<'a>(
+        interval: u64,
+        trades: impl IntoIterator<Item = &'a Trade>,
+    ) -> Self {
+        let mut r = Self::new(interval);
+        for t in trades {
+            r.push(t);
+        }
+        r
+    }
+
+    /// Adds `t` to its candle. Trades of a candle should be pushed in
+    /// order, as they set the open and close.
+    pub fn push(&mut self, t: &Trade) {
+        let start = t.timestamp - t.timestamp % self.interval;
+        let candles = self.markets.entry(t.market).or_default();
+
+        match candles.binary_search_by_key(&start, |c| c.start) {
+            Ok(i) => {
+                let c = &mut candles[i];
+                c.high = c.high.max(t.price);
+                c.low = c.low.min(t.price);
+                c.close = t.price;
+                c.volume += t.size;
+                c.quote_volume += t.size * t.price;
+                c.trades += 1;
+            }
+            Err(i) => candles.insert(i, Candle::new(start, t)),
+        }
+    }
+
+    /// Candles of the dex market, oldest first.
+    pub fn get(&self, market: &Pubkey) -> &[Candle] {
+        self.markets.get(market).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn markets(&self) -> impl Iterator<Item = &Pubkey> {
+        self.markets.keys()
+    }
+}
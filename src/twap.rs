@@ -0,0 +1,88 @@
+//! Replica of the sampling of `MarkCache.twap`.
+//!
+//! The funding period is split into slots of `TWAP_SAMPLE_PERIOD`
+//! seconds. `open`, `high`, `low` and `close` describe the slot starting
+//! at `last_sample_start_time`, and `cumul_avg` is the mean close of the
+//! slots of the funding period so far, the current one included. Slots
+//! without samples count with the close of the slot before them.
+
+use crate::{
+    funding::{FUNDING_PERIOD, TWAP_SAMPLE_PERIOD},
+    TwapInfo,
+};
+use fixed::types::I80F48;
+
+/// Start of the slot containing `time`.
+pub fn sample_start(time: u64) -> u64 {
+    time - time % TWAP_SAMPLE_PERIOD
+}
+
+/// Index of the slot containing `time` within its funding period.
+fn slot_index(time: u64) -> u64 {
+    time % FUNDING_PERIOD / TWAP_SAMPLE_PERIOD
+}
+
+/// `twap` after sampling `price` at unix timestamp `time`. Samples older
+/// than the current slot are ignored.
+pub fn update_twap(twap: &TwapInfo, price: I80F48, time: u64) -> TwapInfo {
+    let last_start = twap.last_sample_start_time;
+    let start = sample_start(time);
+
+    if start < last_start {
+        return *twap;
+    }
+
+    let slot = I80F48::from_num(slot_index(start) + 1);
+    let close = I80F48::from(twap.close);
+    let cumul_avg = I80F48::from(twap.cumul_avg);
+
+    let cumul_avg = if last_start == 0 {
+        price
+    } else if start == last_start {
+        (cumul_avg * slot - close + price) / slot
+    } else if start / FUNDING_PERIOD == last_start / FUNDING_PERIOD {
+        let last_slot = I80F48::from_num(slot_index(last_start) + 1);
+        let missed = slot - last_slot - I80F48::ONE;
+        (cumul_avg * last_slot + close * missed + price) / slot
+    } else {
+        (close * (slot - I80F48::ONE) + price) / slot
+    };
+
+    let (open, high, low) = match start == last_start && last_start != 0 {
+        true => (
+            I80F48::from(twap.open),
+            I80F48::from(twap.high).max(price),
+            I80F48::from(twap.low).min(price),
+        ),
+        false => (price, price, price),
+    };
+
+    TwapInfo {
+        cumul_avg: cumul_avg.into(),
+        open: open.into(),
+        high: high.into(),
+        low: low.into(),
+        close: price.into(),
+        last_sample_start_time: start,
+    }
+}
+
+/// `TwapInfo` after sampling `samples` of `(time, price)` in order,
+/// starting from an empty one.
+pub fn replay_twap(
+    samples: impl IntoIterator<Item = (u64, I80F48)>,
+) -> TwapInfo {
+    let zero = I80F48::ZERO.into();
+    let empty = TwapInfo {
+        cumul_avg: zero,
+        open: zero,
+        high: zero,
+        low: zero,
+        close: zero,
+        last_sample_start_time: 0,
+    };
+
+    samples
+        .into_iter()
+        .fold(empty, |twap, (time, price)| update_twap(&twap, price, time))
+}
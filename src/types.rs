@@ -87,6 +87,12 @@ impl std::fmt::Display for Symbol {
     }
 }
 
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", String::from(self))
+    }
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum FractionType {
     Maintenance,
@@ -94,7 +100,7 @@ pub enum FractionType {
     Cancel,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Debug)]
 pub enum OrderType {
     Limit = 0,
     ImmediateOrCancel = 1,
@@ -188,7 +194,7 @@ pub struct PerpMarketInfo {
     _padding: [u8; 320],
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum PerpType {
     Future = 0,
     CallOption = 1,
@@ -221,7 +227,7 @@ pub struct OracleCache {
     pub quote_decimals: u8,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum OracleType {
     Nil = 0,
     Pyth,
@@ -329,7 +335,9 @@ pub struct Control {
     pub open_orders_agg: [OpenOrdersInfo; MAX_MARKETS],
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
 #[repr(u8)]
 pub enum SpecialOrderType {
     TakeProfitMarket,
@@ -396,7 +404,8 @@ impl SpecialOrders {
     }
 }
 
-#[derive(Copy, Clone)]
+/// Ordered from highest to lowest taker rate.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum FeeTier {
     Base,
     Zo2,
@@ -406,3 +415,29 @@ pub enum FeeTier {
     Zo6,
     Msrm,
 }
+
+#[derive(Debug)]
+pub struct InvalidFeeTierError {}
+
+/// From the `fee_tier` of dex orders and events.
+impl TryFrom<u8> for FeeTier {
+    type Error = InvalidFeeTierError;
+    fn try_from(x: u8) -> std::result::Result<Self, Self::Error> {
+        Ok(match x {
+            0 => Self::Base,
+            1 => Self::Zo2,
+            2 => Self::Zo3,
+            3 => Self::Zo4,
+            4 => Self::Zo5,
+            5 => Self::Zo6,
+            6 => Self::Msrm,
+            _ => return Err(Self::Error {}),
+        })
+    }
+}
+
+impl From<FeeTier> for u8 {
+    fn from(x: FeeTier) -> Self {
+        x as u8
+    }
+}
@@ -1,4 +1,6 @@
 use crate::types::{FeeTier, PerpType};
+use crate::ZO_DEX_PID;
+use anchor_lang::prelude::Pubkey;
 
 /// Returns taker rate x/100_000.
 pub fn taker_rate(perp_type: PerpType, fee_tier: FeeTier) -> u16 {
@@ -28,3 +30,43 @@ pub fn taker_rate(perp_type: PerpType, fee_tier: FeeTier) -> u16 {
 pub fn maker_rate(_: PerpType, _: FeeTier) -> u16 {
     0u16
 }
+
+/// `seeds = [state.key().as_ref()]`
+pub fn state_signer_address(state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[state.as_ref()], &crate::ID)
+}
+
+/// `seeds = [authority.key.as_ref(), state.key().as_ref(), b"marginv1".as_ref()]`
+pub fn margin_address(authority: &Pubkey, state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[authority.as_ref(), state.as_ref(), b"marginv1".as_ref()],
+        &crate::ID,
+    )
+}
+
+/// `seeds = [authority.key.as_ref(), state.key().as_ref(), b"sordersv1".as_ref()]`
+pub fn special_orders_address(
+    authority: &Pubkey,
+    state: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[authority.as_ref(), state.as_ref(), b"sordersv1".as_ref()],
+        &crate::ID,
+    )
+}
+
+/// `seeds = [b"heimdallv1".as_ref()]`, i.e. `ZO_HEIMDALL_ID`.
+pub fn heimdall_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"heimdallv1".as_ref()], &crate::ID)
+}
+
+/// Open orders account of a control on a zo dex market, owned by the dex.
+pub fn open_orders_address(
+    control: &Pubkey,
+    dex_market: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[control.as_ref(), dex_market.as_ref()],
+        &ZO_DEX_PID,
+    )
+}
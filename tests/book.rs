@@ -0,0 +1,176 @@
+mod common;
+
+use common::key;
+use zo_abi::{
+    book::{l2_diff, l2_levels, l3_diff, L2Change, L2Level, L3Change},
+    dex::{Side, Slab},
+    test_utils::*,
+};
+
+fn level(price: u64, quantity: u64) -> L2Level {
+    L2Level { price, quantity }
+}
+
+fn change(price: u64, before: u64, after: u64) -> L2Change {
+    L2Change {
+        price,
+        before,
+        after,
+    }
+}
+
+/// Kind, price, low key bits and quantity of each change.
+fn l3(before: &[u8], after: &[u8]) -> Vec<(&'static str, u64, u64, u64)> {
+    let before = Slab::deserialize(before).unwrap();
+    let after = Slab::deserialize(after).unwrap();
+
+    l3_diff(&before, &after)
+        .into_iter()
+        .map(|c| {
+            let (kind, x) = match c {
+                L3Change::Added(x) => ("added", x),
+                L3Change::Removed(x) => ("removed", x),
+                L3Change::SizeChanged { before, after } => {
+                    assert_eq!({ before.key }, { after.key });
+                    ("resized", after)
+                }
+            };
+            (kind, x.price().get(), c.key() as u64, x.quantity)
+        })
+        .collect()
+}
+
+#[test]
+fn l3_diff_on_asks() {
+    let before = SlabBuilder::new(Side::Ask)
+        .add_order(100, 1, 5, key(1), 0)
+        .add_order(101, 2, 5, key(1), 0)
+        .add_order(102, 3, 5, key(2), 0)
+        .build_bytes();
+    let after = SlabBuilder::new(Side::Ask)
+        .add_order(101, 2, 3, key(1), 0)
+        .add_order(102, 3, 5, key(2), 0)
+        .add_order(103, 4, 7, key(2), 0)
+        .add_order(99, 5, 1, key(2), 0)
+        .build_bytes();
+
+    // Ask keys hold the sequence number in the low bits.
+    assert_eq!(
+        l3(&before, &after),
+        [
+            ("removed", 100, 1, 5),
+            ("resized", 101, 2, 3),
+            ("added", 99, 5, 1),
+            ("added", 103, 4, 7),
+        ]
+    );
+    assert!(l3(&after, &after).is_empty());
+}
+
+#[test]
+fn l3_diff_on_bids_is_best_first() {
+    let before = SlabBuilder::new(Side::Bid)
+        .add_order(90, 1, 5, key(1), 0)
+        .add_order(95, 2, 5, key(1), 0)
+        .build_bytes();
+    let after = SlabBuilder::new(Side::Bid)
+        .add_order(95, 2, 4, key(1), 0)
+        .add_order(96, 3, 1, key(2), 0)
+        .add_order(91, 4, 1, key(2), 0)
+        .build_bytes();
+    let changes = l3(&before, &after);
+    let prices: Vec<_> = changes.iter().map(|x| (x.0, x.1)).collect();
+
+    assert_eq!(
+        prices,
+        [
+            ("removed", 90),
+            ("resized", 95),
+            ("added", 96),
+            ("added", 91)
+        ]
+    );
+}
+
+#[test]
+fn l2_levels_best_first() {
+    let asks = SlabBuilder::new(Side::Ask)
+        .add_order(101, 1, 2, key(1), 0)
+        .add_order(100, 2, 3, key(1), 0)
+        .add_order(101, 3, 4, key(2), 0)
+        .build_bytes();
+    let asks = Slab::deserialize(&asks).unwrap();
+    assert_eq!(l2_levels(&asks), [level(100, 3), level(101, 6)]);
+
+    let bids = SlabBuilder::new(Side::Bid)
+        .add_order(90, 1, 2, key(1), 0)
+        .add_order(95, 2, 3, key(1), 0)
+        .add_order(90, 3, 4, key(2), 0)
+        .add_order(80, 4, 1, key(2), 0)
+        .build_bytes();
+    let bids = Slab::deserialize(&bids).unwrap();
+    assert_eq!(l2_levels(&bids), [level(95, 3), level(90, 6), level(80, 1)]);
+
+    let empty = SlabBuilder::new(Side::Bid).build_bytes();
+    assert!(l2_levels(&Slab::deserialize(&empty).unwrap()).is_empty());
+}
+
+#[test]
+fn l2_diff_best_first() {
+    let before = SlabBuilder::new(Side::Bid)
+        .add_order(90, 1, 2, key(1), 0)
+        .add_order(95, 2, 3, key(1), 0)
+        .add_order(80, 3, 1, key(2), 0)
+        .build_bytes();
+    let after = SlabBuilder::new(Side::Bid)
+        .add_order(90, 1, 2, key(1), 0)
+        .add_order(95, 2, 1, key(1), 0)
+        .add_order(96, 4, 5, key(2), 0)
+        .build_bytes();
+    let before = Slab::deserialize(&before).unwrap();
+    let after = Slab::deserialize(&after).unwrap();
+
+    assert_eq!(
+        l2_diff(&before, &after),
+        [change(96, 0, 5), change(95, 3, 1), change(80, 1, 0)]
+    );
+
+    let asks = SlabBuilder::new(Side::Ask)
+        .add_order(100, 1, 2, key(1), 0)
+        .build_bytes();
+    let asks_after = SlabBuilder::new(Side::Ask)
+        .add_order(100, 1, 2, key(1), 0)
+        .add_order(99, 2, 1, key(1), 0)
+        .add_order(105, 3, 1, key(1), 0)
+        .build_bytes();
+    let asks = Slab::deserialize(&asks).unwrap();
+    let asks_after = Slab::deserialize(&asks_after).unwrap();
+    assert_eq!(
+        l2_diff(&asks, &asks_after),
+        [change(99, 0, 1), change(105, 0, 1)]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Cannot diff slabs of different sides")]
+fn rejects_mismatched_sides() {
+    let bids = SlabBuilder::new(Side::Bid).build_bytes();
+    let asks = SlabBuilder::new(Side::Ask).build_bytes();
+
+    l2_diff(
+        &Slab::deserialize(&bids).unwrap(),
+        &Slab::deserialize(&asks).unwrap(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Cannot diff slabs of different sides")]
+fn l3_rejects_mismatched_sides() {
+    let bids = SlabBuilder::new(Side::Bid).build_bytes();
+    let asks = SlabBuilder::new(Side::Ask).build_bytes();
+
+    l3_diff(
+        &Slab::deserialize(&bids).unwrap(),
+        &Slab::deserialize(&asks).unwrap(),
+    );
+}
@@ -0,0 +1,39 @@
+//! Fixtures shared by the integration tests.
+
+// Each test crate uses a different part of this module.
+#![allow(dead_code)]
+
+use anchor_lang::prelude::Pubkey;
+use zo_abi::{test_utils::StateBuilder, PerpType, State};
+
+pub fn key(x: u8) -> Pubkey {
+    Pubkey::new_from_array([x; 32])
+}
+
+pub const USDC_MINT: u8 = 1;
+pub const BTC_MINT: u8 = 2;
+/// Dex market of BTC-PERP.
+pub const BTC_PERP: u8 = 3;
+
+/// USDC and BTC collaterals, and BTC-PERP with lots of 100 smol BTC and
+/// 10 smol USDC at a 10% initial fraction, so a price of 200_000 lots is
+/// 20_000 USDC.
+pub fn state_builder() -> StateBuilder {
+    StateBuilder::new()
+        .add_collateral(key(USDC_MINT), "USDC", 6, 1000)
+        .add_collateral(key(BTC_MINT), "BTC", 6, 900)
+        .add_perp_market(
+            "BTC-PERP",
+            "BTC",
+            PerpType::Future,
+            6,
+            100,
+            10,
+            key(BTC_PERP),
+        )
+        .market_params(0, 100)
+}
+
+pub fn state() -> State {
+    state_builder().build()
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use bytemuck::Zeroable;
+use common::key;
+use fixed::types::I80F48;
+use solana_program::instruction::Instruction;
+use zo_abi::{
+    crank::{
+        cache_oracle_ix, consume_events_ixs, crank_pnl_ixs, CacheOracleError,
+    },
+    dex::ZoDexMarket,
+    open_orders_address,
+    test_utils::*,
+    OracleType, PerpType,
+};
+
+fn market() -> ZoDexMarket {
+    let mut m = ZoDexMarket::zeroed();
+    m.own_address = key(100);
+    m.event_q = key(101);
+    m
+}
+
+/// Remaining accounts of `ix` after its `fixed` named ones.
+fn remaining(ix: &Instruction, fixed: usize) -> Vec<Pubkey> {
+    ix.accounts[fixed..].iter().map(|x| x.pubkey).collect()
+}
+
+fn oo(control: u8) -> Pubkey {
+    open_orders_address(&key(control), &key(100)).0
+}
+
+#[test]
+fn consume_events_chunks_controls_then_open_orders() {
+    let controls: Vec<_> = (1..=5).map(key).collect();
+    // Fee payer, program, 5 named accounts and 2 users.
+    let ixs = consume_events_ixs(&key(0), &market(), &controls, 10, 11);
+
+    let sizes: Vec<_> = ixs.iter().map(|x| x.accounts.len() - 5).collect();
+    assert_eq!(sizes, [4, 4, 2]);
+    assert_eq!(remaining(&ixs[0], 5), [key(1), key(2), oo(1), oo(2)]);
+    assert_eq!(remaining(&ixs[1], 5), [key(3), key(4), oo(3), oo(4)]);
+    assert_eq!(remaining(&ixs[2], 5), [key(5), oo(5)]);
+    assert!(ixs
+        .iter()
+        .all(|x| x.accounts[5..].iter().all(|a| a.is_writable)));
+
+    let accounts = &ixs[0].accounts;
+    assert_eq!(accounts[3].pubkey, key(100));
+    assert_eq!(accounts[4].pubkey, key(101));
+    assert_eq!(ixs[0].data, ixs[1].data);
+}
+
+#[test]
+fn consume_events_fits_one_user_at_least() {
+    let controls = [key(1), key(2)];
+    let ixs = consume_events_ixs(&key(0), &market(), &controls, 10, 0);
+
+    assert_eq!(ixs.len(), 2);
+    assert_eq!(remaining(&ixs[1], 5), [key(2), oo(2)]);
+}
+
+#[test]
+fn crank_pnl_appends_margins() {
+    let controls: Vec<_> = (1..=4).map(key).collect();
+    // Control 3 has no known margin.
+    let margin_of =
+        |c: &Pubkey| (*c != key(3)).then(|| key(c.to_bytes()[0] + 50));
+    let ixs =
+        crank_pnl_ixs(&key(0), &key(9), &market(), &controls, margin_of, 13);
+
+    assert_eq!(ixs.len(), 2);
+    assert_eq!(
+        remaining(&ixs[0], 5),
+        [key(1), key(2), oo(1), oo(2), key(51), key(52)]
+    );
+    assert_eq!(remaining(&ixs[1], 5), [key(4), oo(4), key(54)]);
+}
+
+#[test]
+fn cache_oracle_orders_sources_then_markets() {
+    let state = StateBuilder::new()
+        .cache(key(9))
+        .add_perp_market(
+            "BTC-PERP",
+            "BTC",
+            PerpType::Future,
+            6,
+            100,
+            10,
+            key(20),
+        )
+        .add_perp_market(
+            "SOL-PERP",
+            "SOL",
+            PerpType::Future,
+            9,
+            100,
+            10,
+            key(21),
+        )
+        .add_perp_market(
+            "ETH-PERP",
+            "ETH",
+            PerpType::Future,
+            6,
+            100,
+            10,
+            key(22),
+        )
+        .build();
+    let cache = CacheBuilder::new()
+        .set_oracle_price("BTC", I80F48::ONE, 6, 6)
+        .set_oracle_price("SOL", I80F48::ONE, 9, 6)
+        .set_oracle_price("ETH", I80F48::ONE, 6, 6)
+        .set_oracle_sources(
+            "BTC",
+            &[
+                (OracleType::Pyth, key(10)),
+                (OracleType::Nil, key(12)),
+                (OracleType::Switchboard, key(11)),
+            ],
+        )
+        .set_oracle_sources("SOL", &[(OracleType::Pyth, key(13))])
+        .set_oracle_sources("ETH", &[(OracleType::Pyth, key(14))])
+        .build();
+
+    let symbols = ["SOL".to_string(), "BTC".to_string()];
+    let ix =
+        cache_oracle_ix(&key(1), &key(2), &state, &cache, &symbols).unwrap();
+
+    let named: Vec<_> = ix.accounts[..3].iter().map(|x| x.pubkey).collect();
+    assert_eq!(named, [key(1), key(2), key(9)]);
+    // Sources by `symbols`, then the dex markets in state order.
+    assert_eq!(
+        remaining(&ix, 4),
+        [key(13), key(10), key(11), key(20), key(21)]
+    );
+    assert!(ix.accounts[4..7].iter().all(|x| !x.is_writable));
+    assert!(ix.accounts[7..].iter().all(|x| x.is_writable));
+
+    let e = cache_oracle_ix(&key(1), &key(2), &state, &cache, &["DOGE".into()]);
+    assert_eq!(e, Err(CacheOracleError::UnknownSymbol("DOGE".into())));
+}
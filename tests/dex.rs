@@ -0,0 +1,179 @@
+mod common;
+
+use common::key;
+use zo_abi::{
+    dex::{OrderId, Side, Slab},
+    test_utils::*,
+};
+
+/// Offset of the first node, the root, in `SlabBuilder` data.
+const ROOT: usize = 45;
+
+fn asks() -> Vec<u8> {
+    SlabBuilder::new(Side::Ask)
+        .add_order(100, 7, 1, key(1), 11)
+        .add_order(100, 9, 2, key(2), 12)
+        .add_order(101, 8, 3, key(1), 13)
+        .add_order(250, 3, 4, key(2), 14)
+        .build_bytes()
+}
+
+fn bids() -> Vec<u8> {
+    SlabBuilder::new(Side::Bid)
+        .add_order(99, 4, 1, key(1), 21)
+        .add_order(99, 6, 2, key(2), 22)
+        .add_order(12, 5, 3, key(1), 23)
+        .build_bytes()
+}
+
+fn keys(slab: &Slab) -> Vec<u128> {
+    slab.iter_front().map(|x| x.key).collect()
+}
+
+#[test]
+fn finds_every_order_id() {
+    for buf in [asks(), bids()] {
+        let slab = Slab::deserialize(&buf).unwrap();
+        for k in keys(&slab) {
+            assert_eq!(slab.find_order_id(k).map(|x| x.key), Some(k));
+        }
+    }
+}
+
+#[test]
+fn misses_unknown_order_ids() {
+    let buf = asks();
+    let slab = Slab::deserialize(&buf).unwrap();
+
+    // Same price as an order, other sequence numbers.
+    assert!(slab.find_order_id(100 << 64 | 8).is_none());
+    assert!(slab.find_order_id(250 << 64).is_none());
+    assert!(slab.find_order_id(0).is_none());
+    assert!(slab.find_order_id(u128::MAX).is_none());
+
+    let buf = SlabBuilder::new(Side::Ask).build_bytes();
+    let slab = Slab::deserialize(&buf).unwrap();
+    assert!(slab.find_order_id(100 << 64 | 7).is_none());
+}
+
+#[test]
+fn finds_bids_by_inverted_keys() {
+    let buf = bids();
+    let slab = Slab::deserialize(&buf).unwrap();
+
+    let x = slab.find_order_id(99 << 64 | !4u64 as u128).unwrap();
+    assert_eq!({ x.client_order_id }, 21);
+    let x = slab.find_order_id(12 << 64 | !5u64 as u128).unwrap();
+    assert_eq!({ x.client_order_id }, 23);
+    // The ask key of the same order.
+    assert!(slab.find_order_id(99 << 64 | 4).is_none());
+}
+
+#[test]
+fn finds_client_order_ids() {
+    let buf = asks();
+    let slab = Slab::deserialize(&buf).unwrap();
+
+    let x = slab.find_client_order_id(&key(2), 14).unwrap();
+    assert_eq!(x.price().get(), 250);
+    assert!(slab.find_client_order_id(&key(1), 14).is_none());
+    assert_eq!(slab.iter_control(&key(1)).count(), 2);
+}
+
+#[test]
+fn rejects_prefix_len_past_key() {
+    let mut buf = asks();
+    // Root is an inner node, its `prefix_len` follows the tag.
+    assert_eq!(buf[ROOT], 1);
+    buf[ROOT + 4..ROOT + 8].copy_from_slice(&128u32.to_le_bytes());
+    let slab = Slab::deserialize(&buf).unwrap();
+
+    assert!(slab.find_order_id(100 << 64 | 7).is_none());
+}
+
+fn order_ids(buf: &[u8], side: Side) -> Vec<OrderId> {
+    let slab = Slab::deserialize(buf).unwrap();
+    slab.iter_best()
+        .map(|x| OrderId::from_leaf(x, side))
+        .collect()
+}
+
+#[test]
+fn stops_on_cyclic_slabs() {
+    let mut buf = asks();
+    // Root points to itself on both sides.
+    buf[ROOT + 24..ROOT + 32].copy_from_slice(&[0; 8]);
+    let slab = Slab::deserialize(&buf).unwrap();
+
+    assert!(slab.find_order_id(100 << 64 | 7).is_none());
+}
+
+#[test]
+fn validates_slab_trees() {
+    assert!(Slab::is_valid(&asks()));
+    assert!(Slab::is_valid(&bids()));
+    assert!(Slab::is_valid(&SlabBuilder::new(Side::Ask).build_bytes()));
+    assert!(!Slab::is_valid(&asks()[..40]));
+
+    // Root points to itself.
+    let mut buf = asks();
+    buf[ROOT + 24..ROOT + 32].copy_from_slice(&[0; 8]);
+    assert!(!Slab::is_valid(&buf));
+
+    // Both children are the same leaf.
+    let mut buf = asks();
+    buf.copy_within(ROOT + 24..ROOT + 28, ROOT + 28);
+    assert!(!Slab::is_valid(&buf));
+
+    // Child past the end.
+    let mut buf = asks();
+    buf[ROOT + 28..ROOT + 32].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(!Slab::is_valid(&buf));
+
+    // Unknown tag.
+    let mut buf = asks();
+    buf[ROOT..ROOT + 4].copy_from_slice(&9u32.to_le_bytes());
+    assert!(!Slab::is_valid(&buf));
+}
+
+#[test]
+fn order_id_reads_leaf_keys() {
+    let asks = order_ids(&asks(), Side::Ask);
+    let bids = order_ids(&bids(), Side::Bid);
+
+    // Keys as the dex writes them, not through `OrderId::new`.
+    assert_eq!(asks[0].key, 0x64_0000_0000_0000_0007);
+    assert_eq!(bids[0].key, 0x63_ffff_ffff_ffff_fffb);
+
+    let read = |x: &Vec<OrderId>| {
+        x.iter()
+            .map(|x| (x.price(), x.seq_num()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(read(&asks), [(100, 7), (100, 9), (101, 8), (250, 3)]);
+    assert_eq!(read(&bids), [(99, 4), (99, 6), (12, 5)]);
+
+    for x in asks.iter().chain(&bids) {
+        assert_eq!(OrderId::new(x.side, x.price(), x.seq_num()), *x);
+    }
+    assert!(bids.iter().all(OrderId::is_long));
+    assert!(!asks.iter().any(OrderId::is_long));
+    assert_eq!(bids[0].cancel_args(), (Some(bids[0].key), Some(true)));
+}
+
+#[test]
+fn order_ids_sort_oldest_first() {
+    let mut ids = order_ids(&asks(), Side::Ask);
+    ids.extend(order_ids(&bids(), Side::Bid));
+    ids.sort();
+
+    let seq_nums: Vec<_> = ids.iter().map(OrderId::seq_num).collect();
+    assert_eq!(seq_nums, [3, 4, 5, 6, 7, 8, 9]);
+
+    // Bids first at the same sequence number, then by key.
+    let bid = OrderId::new(Side::Bid, 1, 10);
+    let ask = OrderId::new(Side::Ask, 1, 10);
+    let cheap = OrderId::new(Side::Ask, 0, 10);
+    assert!(bid < cheap && cheap < ask);
+    assert!(OrderId::new(Side::Ask, u64::MAX, 0) < cheap);
+}
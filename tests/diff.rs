@@ -0,0 +1,49 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::{key, state, BTC_PERP};
+use zo_abi::{
+    diff::{SpecialOrderChange, SpecialOrderSummary},
+    SpecialOrderType, SpecialOrdersInfo,
+};
+
+fn order(market: Pubkey) -> SpecialOrdersInfo {
+    SpecialOrdersInfo {
+        id: 3,
+        market,
+        ty: SpecialOrderType::StopLossLimit,
+        is_long: false,
+        trigger_price: 19_500_000_000,
+        limit_price: 19_400_000_000,
+        size: 5_000,
+        fee: 0,
+    }
+}
+
+#[test]
+fn special_orders_in_big_units() {
+    let x = SpecialOrderSummary::new(&state(), &order(key(BTC_PERP)));
+
+    assert_eq!(x.symbol.map(String::from).as_deref(), Some("BTC-PERP"));
+    assert_eq!(x.trigger_price, 19_500.0);
+    assert_eq!(x.limit_price, 19_400.0);
+    assert_eq!(x.size, 0.5);
+    assert_eq!(
+        SpecialOrderChange::Added(x).to_string(),
+        "special order 3 added: StopLossLimit short 0.5 BTC-PERP at 19500, \
+         limit 19400"
+    );
+}
+
+#[test]
+fn special_orders_of_unlisted_markets() {
+    let x = SpecialOrderSummary::new(&state(), &order(key(9)));
+
+    assert_eq!(x.symbol, None);
+    assert_eq!(x.size, 5_000.0);
+    assert_eq!(
+        SpecialOrderChange::Removed(x).to_string(),
+        "special order 3 removed: StopLossLimit short 5000 lots of an \
+         unlisted market at 19500, limit 19400"
+    );
+}
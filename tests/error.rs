@@ -0,0 +1,104 @@
+use solana_program::instruction::InstructionError;
+use zo_abi::{
+    error::{DexError, DexErrorCode, ProgramFailure, ZoError, ZoErrorCode},
+    SERUM_DEX_PID, ZO_DEX_PID,
+};
+
+#[test]
+fn dex_codes_round_trip() {
+    for code in (0..63).chain([1000]) {
+        let e = DexErrorCode::from_code(code).unwrap();
+        assert_eq!(e as u32, code);
+    }
+    assert_eq!(DexErrorCode::from_code(63), None);
+    assert_eq!(
+        DexErrorCode::from_code(59),
+        Some(DexErrorCode::OrderNotFound)
+    );
+}
+
+#[test]
+fn dex_assertions() {
+    assert_eq!(
+        DexError::from_code(0x0200_01f4),
+        DexError::Assertion {
+            file_id: 2,
+            line: 500,
+        }
+    );
+    assert_eq!(DexError::from_code(999), DexError::Unknown(999));
+}
+
+#[test]
+fn decodes_dex_failures() {
+    let logs = [
+        format!("Program {} invoke [2]", ZO_DEX_PID),
+        format!("Program {} failed: custom program error: 0x3b", ZO_DEX_PID),
+        format!("Program {} failed: custom program error: 0x3b", zo_abi::ID),
+    ];
+    let f = ProgramFailure::from_logs(&logs).unwrap();
+
+    assert_eq!(f.program_id, ZO_DEX_PID);
+    assert_eq!(f.zo_error(), None);
+    assert_eq!(
+        f.dex_error(),
+        Some(DexError::Code(DexErrorCode::OrderNotFound))
+    );
+    assert_eq!(f.to_string(), "zo dex: OrderNotFound (59): Order not found");
+
+    let f = ProgramFailure {
+        program_id: SERUM_DEX_PID,
+        code: 0x3d,
+    };
+    assert_eq!(
+        f.to_string(),
+        "serum dex: WouldSelfTrade (61): Order would trade with an order of \
+         the same account"
+    );
+}
+
+#[test]
+fn zo_codes_round_trip() {
+    for code in 6000..6030 {
+        let e = ZoErrorCode::from_code(code).unwrap();
+        assert_eq!(e as u32, code);
+        assert_eq!(ZoError::from_code(code), ZoError::Program(e));
+    }
+    assert_eq!(ZoErrorCode::from_code(5999), None);
+    assert_eq!(ZoError::from_code(6030), ZoError::Custom(6030));
+    assert!(matches!(ZoError::from_code(2006), ZoError::Anchor(_)));
+}
+
+#[test]
+fn decodes_zo_failures() {
+    let logs = [
+        format!("Program {} invoke [1]", zo_abi::ID),
+        "Program log: Instruction: LiquidatePerpPosition".to_string(),
+        format!(
+            "Program {} failed: custom program error: 0x1784",
+            zo_abi::ID
+        ),
+    ];
+    let f = ProgramFailure::from_logs(&logs).unwrap();
+
+    assert_eq!(
+        f.zo_error(),
+        Some(ZoError::Program(ZoErrorCode::NotLiquidatable))
+    );
+    assert_eq!(f.dex_error(), None);
+    assert_eq!(
+        f.to_string(),
+        "zo: NotLiquidatable (6020): Margin is not below maintenance"
+    );
+
+    let f = ProgramFailure::from_instruction_error(
+        &zo_abi::ID,
+        &InstructionError::Custom(0x1771),
+        &[] as &[&str],
+    )
+    .unwrap();
+    assert_eq!(
+        f.to_string(),
+        "zo: Unauthorized (6001): Signer is not authorized"
+    );
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::Pubkey;
+use zo_abi::{
+    events::EventFillLog,
+    fees::{fill_notional, FillFeeCheck},
+    FeeTier, PerpType,
+};
+
+fn log(
+    is_long: bool,
+    is_maker: bool,
+    qty_paid: u64,
+    qty_received: u64,
+    fee_or_rebate: u64,
+) -> EventFillLog {
+    EventFillLog {
+        is_long,
+        is_maker,
+        market_key: Pubkey::default(),
+        margin: Pubkey::default(),
+        control: Pubkey::default(),
+        qty_paid,
+        qty_received,
+        fee_or_rebate,
+        discriminator: 0,
+    }
+}
+
+fn check(log: &EventFillLog, tier: FeeTier) -> FillFeeCheck {
+    FillFeeCheck::new(log, PerpType::Future, tier)
+}
+
+#[test]
+fn taker_long_pays_fee_on_top() {
+    // 10 bps of 1_234_567, rounded up.
+    let l = log(true, false, 1_235_802, 5, 1_235);
+    let c = check(&l, FeeTier::Base);
+
+    assert_eq!(fill_notional(&l), 1_234_567);
+    assert_eq!((c.expected, c.actual), (1_235, 1_235));
+    assert!(c.is_consistent());
+
+    // Logged at a cheaper tier than claimed.
+    let c = check(&l, FeeTier::Msrm);
+    assert_eq!(c.expected, 519);
+    assert!(!c.is_consistent());
+}
+
+#[test]
+fn taker_short_receives_less_the_fee() {
+    let l = log(false, false, 5, 999_000, 1_000);
+    let c = check(&l, FeeTier::Base);
+
+    assert_eq!(c.notional, 1_000_000);
+    assert_eq!(c.expected, 1_000);
+    assert!(c.is_consistent());
+
+    // Off by one smol of rounding is accepted.
+    let c = check(&log(false, false, 5, 998_999, 1_001), FeeTier::Base);
+    assert_eq!(c.notional, 1_000_000);
+    assert!(c.is_consistent());
+}
+
+#[test]
+fn maker_long_pays_less_the_rebate() {
+    let l = log(true, true, 999_990, 5, 10);
+    let c = check(&l, FeeTier::Base);
+
+    assert_eq!(c.notional, 1_000_000);
+    // Makers earn no rebate at any tier.
+    assert_eq!(c.expected, 0);
+    assert!(!c.is_consistent());
+    assert!(
+        check(&log(true, true, 1_000_000, 5, 0), FeeTier::Zo6).is_consistent()
+    );
+}
+
+#[test]
+fn maker_short_receives_the_rebate_on_top() {
+    let l = log(false, true, 5, 1_000_010, 10);
+    let c = check(&l, FeeTier::Base);
+
+    assert_eq!(c.notional, 1_000_000);
+    assert_eq!((c.expected, c.actual), (0, 10));
+    assert!(!c.is_consistent());
+    assert!(check(&log(false, true, 5, 1_000_000, 0), FeeTier::Base)
+        .is_consistent());
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{key, state};
+use fixed::types::I80F48;
+use zo_abi::{
+    health::{margin_fraction, position_exposure, MarginHealth},
+    test_utils::*,
+    Cache, Control, FractionType, Margin, State,
+};
+
+fn n(x: i64) -> I80F48 {
+    I80F48::from_num(x)
+}
+
+/// Fixed-point division leaves errors far below a smol.
+fn assert_close(a: I80F48, b: I80F48) {
+    assert!((a - b).abs() < n(1) / 1_000, "{} != {}", a, b);
+}
+
+/// USDC, BTC weighted at 90%, and BTC-PERP at a 10% initial fraction,
+/// all at 20_000 USDC per BTC.
+fn accounts(usdc: i64, btc: i64) -> (State, Cache, Margin) {
+    let state = state();
+    let cache = CacheBuilder::new()
+        .set_oracle_price("BTC", n(20_000), 6, 6)
+        .set_mark_price(0, n(20_000))
+        .build();
+    let margin = MarginBuilder::new(key(4), key(5))
+        .set_collateral(0, n(usdc))
+        .set_collateral(1, n(btc))
+        .build();
+    (state, cache, margin)
+}
+
+fn health(
+    (state, cache, margin): &(State, Cache, Margin),
+    control: &Control,
+    ty: FractionType,
+) -> MarginHealth {
+    MarginHealth::new(state, cache, margin, control, ty)
+}
+
+#[test]
+fn margin_fractions() {
+    // In parts per million.
+    let f = |ty| margin_fraction(100, ty) * n(1_000_000);
+
+    assert_close(f(FractionType::Initial), n(100_000));
+    assert_close(f(FractionType::Cancel), n(75_000));
+    assert_close(f(FractionType::Maintenance), n(50_000));
+}
+
+#[test]
+fn weights_spot_collateral() {
+    let a = accounts(1_000_000_000, 1_000_000);
+    let control = ControlBuilder::new(key(4)).build();
+    let h = health(&a, &control, FractionType::Initial);
+
+    // 1_000 USDC plus 90% of 20_000 USDC of BTC.
+    assert_close(h.collateral, n(19_000_000_000));
+    assert_close(h.requirement, n(0));
+}
+
+#[test]
+fn borrows_count_fully_with_a_requirement() {
+    let a = accounts(-1_000_000_000, 1_000_000);
+    let control = ControlBuilder::new(key(4)).build();
+
+    let h = health(&a, &control, FractionType::Initial);
+    assert_close(h.collateral, n(17_000_000_000));
+    assert_close(h.requirement, n(100_000_000));
+
+    let h = health(&a, &control, FractionType::Maintenance);
+    assert_close(h.requirement, n(30_000_000));
+}
+
+#[test]
+fn perp_pnl_and_exposure() {
+    let a = accounts(1_000_000_000, 0);
+    // Long 1 BTC bought at 19_000, with 0.5 BTC more bid.
+    let control = ControlBuilder::new(key(4))
+        .set_position(0, key(6), 1_000_000, -19_000_000_000)
+        .set_orders(0, 500_000, 0, 1)
+        .build();
+
+    let oo = &control.open_orders_agg[0];
+    assert_eq!(position_exposure(oo), n(1_500_000));
+
+    let h = health(&a, &control, FractionType::Initial);
+    assert_close(h.collateral, n(2_000_000_000));
+    assert_close(h.requirement, n(3_000_000_000));
+    assert!(!h.is_healthy());
+
+    let h = health(&a, &control, FractionType::Maintenance);
+    assert_close(h.requirement, n(1_500_000_000));
+    assert!(h.is_healthy());
+}
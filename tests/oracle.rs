@@ -0,0 +1,136 @@
+use bytemuck::{bytes_of, PodCastError, Zeroable};
+use fixed::types::I80F48;
+use zo_abi::{
+    oracle::{parse_oracle, OracleError, PythPrice, SwitchboardAggregator},
+    OracleType,
+};
+
+/// Size of a pyth v2 price account with all its publishers.
+const PYTH_LEN: usize = 3312;
+
+fn pyth(expo: i32, agg_price: i64, agg_conf: u64) -> Vec<u8> {
+    let mut p = PythPrice::zeroed();
+    p.magic = 0xa1b2c3d4;
+    p.ver = 2;
+    p.atype = 3;
+    p.expo = expo;
+    p.timestamp = 1_650_000_000;
+    p.agg_price = agg_price;
+    p.agg_conf = agg_conf;
+    p.agg_status = 1;
+
+    let mut buf = bytes_of(&p).to_vec();
+    buf.resize(PYTH_LEN, 0);
+    buf
+}
+
+fn switchboard(result: (i128, u32), std_deviation: (i128, u32)) -> Vec<u8> {
+    let mut a = SwitchboardAggregator::zeroed();
+    a.round_open_timestamp = 1_650_000_100;
+    a.result_mantissa = result.0;
+    a.result_scale = result.1;
+    a.std_deviation_mantissa = std_deviation.0;
+    a.std_deviation_scale = std_deviation.1;
+
+    let mut buf = bytes_of(&a).to_vec();
+    buf[..8].copy_from_slice(&[217, 230, 65, 101, 201, 162, 27, 125]);
+    buf
+}
+
+#[track_caller]
+fn assert_close(a: I80F48, b: f64) {
+    assert!((a.to_num::<f64>() - b).abs() < 1e-9, "{} != {}", a, b);
+}
+
+#[test]
+fn pyth_prices_in_smol() {
+    // 20_000.00000000 +- 1.5, with 8 decimals.
+    let buf = pyth(-8, 2_000_000_000_000, 150_000_000);
+
+    let p = parse_oracle(OracleType::Pyth, &buf, 6, 6).unwrap().unwrap();
+    assert_close(p.price, 20_000.0);
+    assert_close(p.confidence, 1.5);
+    assert_eq!(p.expo, -8);
+    assert_eq!(p.publish_time, 1_650_000_000);
+
+    // An asset with 9 decimals quoted in 6.
+    let p = parse_oracle(OracleType::Pyth, &buf, 9, 6).unwrap().unwrap();
+    assert_close(p.price, 20.0);
+    assert_close(p.confidence, 0.0015);
+
+    // And with fewer decimals than its quote.
+    let p = parse_oracle(OracleType::Pyth, &buf, 2, 6).unwrap().unwrap();
+    assert_close(p.price, 200_000_000.0);
+
+    assert!(PythPrice::deserialize(&buf).unwrap().is_trading());
+}
+
+#[test]
+fn pyth_positive_exponent() {
+    let buf = pyth(3, 25, 1);
+    let p = parse_oracle(OracleType::Pyth, &buf, 6, 6).unwrap().unwrap();
+
+    assert_close(p.price, 25_000.0);
+    assert_close(p.confidence, 1_000.0);
+
+    // Past the range of an `I80F48`.
+    let buf = pyth(20, i64::MAX, 0);
+    assert_eq!(parse_oracle(OracleType::Pyth, &buf, 6, 6), Ok(None));
+}
+
+#[test]
+fn switchboard_prices_in_smol() {
+    // 20_000.50 +- 1.5.
+    let buf = switchboard((2_000_050, 2), (15, 1));
+
+    let p = parse_oracle(OracleType::Switchboard, &buf, 6, 6)
+        .unwrap()
+        .unwrap();
+    assert_close(p.price, 20_000.5);
+    assert_close(p.confidence, 1.5);
+    assert_eq!(p.expo, -2);
+    assert_eq!(p.publish_time, 1_650_000_100);
+
+    let p = parse_oracle(OracleType::Switchboard, &buf, 8, 6)
+        .unwrap()
+        .unwrap();
+    assert_close(p.price, 200.005);
+    assert_close(p.confidence, 0.015);
+}
+
+#[test]
+fn switchboard_drops_digits_past_precision() {
+    // 1.000...0001 with 28 decimals.
+    let m = 10i128.pow(28) + 1;
+    let buf = switchboard((m, 28), (0, 0));
+    let p = parse_oracle(OracleType::Switchboard, &buf, 6, 6)
+        .unwrap()
+        .unwrap();
+
+    assert_close(p.price, 1.0);
+}
+
+#[test]
+fn rejects_mismatched_accounts() {
+    let pyth = pyth(-8, 1, 1);
+    let sb = switchboard((1, 0), (0, 0));
+
+    assert!(PythPrice::is_valid(&pyth));
+    assert!(!PythPrice::is_valid(&sb));
+    assert!(SwitchboardAggregator::is_valid(&sb));
+    assert!(!SwitchboardAggregator::is_valid(&pyth));
+
+    assert_eq!(
+        parse_oracle(OracleType::Switchboard, &pyth, 6, 6),
+        Err(OracleError::InvalidAccount)
+    );
+    assert_eq!(
+        parse_oracle(OracleType::Pyth, &sb, 6, 6),
+        Err(OracleError::InvalidAccount)
+    );
+    assert_eq!(
+        parse_oracle(OracleType::Pyth, &pyth[..100], 6, 6),
+        Err(OracleError::Pod(PodCastError::SizeMismatch))
+    );
+    assert_eq!(parse_oracle(OracleType::Nil, &[], 6, 6), Ok(None));
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use bytemuck::Zeroable;
+use common::{key, state};
+use fixed::types::I80F48;
+use zo_abi::{
+    dex::{Side, Slab, ZoDexMarket},
+    preflight::{
+        preflight_order, OrderError, OrderPreflight, OrderRequest,
+        OrderWarning, TraderAccounts,
+    },
+    test_utils::*,
+    Cache, Control, Margin, OrderType,
+};
+
+fn cache() -> Cache {
+    CacheBuilder::new()
+        .set_mark_price(0, I80F48::from_num(20_000))
+        .build()
+}
+
+fn margin(usdc: i64) -> Margin {
+    MarginBuilder::new(key(3), key(4))
+        .set_collateral(0, I80F48::from_num(usdc))
+        .build()
+}
+
+/// A price of 200_000 lots is 20_000 USDC, and one lot is 0.0001 BTC.
+fn market() -> ZoDexMarket {
+    let mut m = ZoDexMarket::zeroed();
+    m.coin_lot_size = 100;
+    m.pc_lot_size = 10;
+    m.coin_decimals = 6;
+    m
+}
+
+fn order(is_long: bool, price: f64, order_type: OrderType) -> OrderRequest {
+    OrderRequest {
+        is_long,
+        price,
+        size: 1.0,
+        order_type,
+        max_quote_quantity: None,
+    }
+}
+
+fn preflight(
+    req: &OrderRequest,
+    book: &[u8],
+    margin: &Margin,
+    control: &Control,
+) -> Result<OrderPreflight, OrderError> {
+    let (state, cache) = (state(), cache());
+    let accounts = TraderAccounts {
+        state: &state,
+        cache: &cache,
+        margin,
+        control,
+        market_index: 0,
+    };
+    let book = Slab::deserialize(book).unwrap();
+    preflight_order(req, &market(), &book, &accounts)
+}
+
+fn asks() -> Vec<u8> {
+    SlabBuilder::new(Side::Ask)
+        .add_order(200_000, 1, 10, key(9), 0)
+        .build_bytes()
+}
+
+fn bids() -> Vec<u8> {
+    SlabBuilder::new(Side::Bid)
+        .add_order(199_000, 1, 10, key(9), 0)
+        .build_bytes()
+}
+
+fn long_position(size: i64) -> Control {
+    ControlBuilder::new(key(3))
+        .set_position(0, key(5), size, -size * 20_000)
+        .build()
+}
+
+#[test]
+fn converts_to_lots() {
+    let control = ControlBuilder::new(key(3)).build();
+    let req = order(true, 19_990.0, OrderType::Limit);
+    let r = preflight(&req, &asks(), &margin(10_000_000_000), &control);
+
+    assert_eq!(
+        r,
+        Ok(OrderPreflight {
+            limit_price: 199_900,
+            max_base_quantity: 10_000,
+            max_quote_quantity: 19_990_000_000,
+            warnings: Vec::new(),
+        })
+    );
+}
+
+#[test]
+fn reduce_only_on_wrong_side() {
+    let m = margin(10_000_000_000);
+    let req = order(true, 19_000.0, OrderType::ReduceOnlyLimit);
+
+    let flat = ControlBuilder::new(key(3)).build();
+    assert_eq!(
+        preflight(&req, &asks(), &m, &flat),
+        Err(OrderError::NotReducing)
+    );
+    assert_eq!(
+        preflight(&req, &asks(), &m, &long_position(1_000_000)),
+        Err(OrderError::NotReducing)
+    );
+
+    let req = order(false, 21_000.0, OrderType::ReduceOnlyIoc);
+    let r = preflight(&req, &bids(), &m, &long_position(500_000)).unwrap();
+    assert_eq!(r.warnings, [OrderWarning::ReduceOnlyExceedsPosition]);
+}
+
+#[test]
+fn post_only_crossing() {
+    let m = margin(10_000_000_000);
+    let flat = ControlBuilder::new(key(3)).build();
+
+    let req = order(true, 20_000.0, OrderType::PostOnly);
+    assert_eq!(
+        preflight(&req, &asks(), &m, &flat),
+        Err(OrderError::PostOnlyWouldCross)
+    );
+    let req = order(true, 19_999.0, OrderType::PostOnly);
+    assert!(preflight(&req, &asks(), &m, &flat).is_ok());
+
+    let req = order(false, 19_900.0, OrderType::PostOnly);
+    assert_eq!(
+        preflight(&req, &bids(), &m, &flat),
+        Err(OrderError::PostOnlyWouldCross)
+    );
+    let req = order(false, 19_901.0, OrderType::PostOnly);
+    assert!(preflight(&req, &bids(), &m, &flat).is_ok());
+}
+
+#[test]
+fn insufficient_margin_at_boundary() {
+    let flat = ControlBuilder::new(key(3)).build();
+    let req = order(true, 19_000.0, OrderType::Limit);
+
+    // 1 BTC at a mark of 20_000 and 10% requires 2_000 USDC.
+    assert!(preflight(&req, &asks(), &margin(2_000_000_000), &flat).is_ok());
+    assert_eq!(
+        preflight(&req, &asks(), &margin(1_999_999_999), &flat),
+        Err(OrderError::InsufficientMargin)
+    );
+}
+
+#[test]
+fn rejects_wrong_book_side() {
+    let flat = ControlBuilder::new(key(3)).build();
+    let req = order(true, 19_000.0, OrderType::Limit);
+
+    assert_eq!(
+        preflight(&req, &bids(), &margin(10_000_000_000), &flat),
+        Err(OrderError::WrongBookSide)
+    );
+}
+
+#[test]
+fn rejects_overflowing_orders() {
+    let flat = ControlBuilder::new(key(3)).build();
+    let m = margin(10_000_000_000);
+
+    // 1e12 BTC at 20_000 is past a u64 of smol quote.
+    let req = OrderRequest {
+        size: 1e12,
+        ..order(true, 20_000.0, OrderType::Limit)
+    };
+    assert_eq!(
+        preflight(&req, &asks(), &m, &flat),
+        Err(OrderError::Overflow)
+    );
+
+    // Added to the resting orders.
+    let full = ControlBuilder::new(key(3))
+        .set_orders(0, 0, u64::MAX, 1)
+        .build();
+    let req = order(false, 21_000.0, OrderType::Limit);
+    assert_eq!(
+        preflight(&req, &bids(), &m, &full),
+        Err(OrderError::Overflow)
+    );
+}
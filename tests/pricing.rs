@@ -0,0 +1,101 @@
+use zo_abi::{
+    pricing::{
+        everlasting_call, everlasting_put, fair_value, square_perp,
+        PricingParams, Valuation,
+    },
+    PerpType,
+};
+
+fn params(volatility: f64, funding_period: f64) -> PricingParams {
+    PricingParams {
+        volatility,
+        funding_period,
+    }
+}
+
+const DAILY: PricingParams = PricingParams {
+    volatility: 0.8,
+    funding_period: 1. / 365.,
+};
+
+#[track_caller]
+fn assert_close(a: f64, b: f64, tol: f64) {
+    assert!((a - b).abs() <= tol, "{} != {}", a, b);
+}
+
+fn call(spot: f64) -> Valuation {
+    everlasting_call(spot, 100., &DAILY).unwrap()
+}
+
+#[test]
+fn call_is_smooth_at_the_strike() {
+    let (below, at, above) = (call(100. - 1e-9), call(100.), call(100. + 1e-9));
+
+    assert_close(below.mark, above.mark, 1e-6);
+    assert_close(below.delta, above.delta, 1e-6);
+    assert_close(at.mark, above.mark, 1e-6);
+    assert!(at.mark > 0.);
+}
+
+#[test]
+fn call_greeks_match_finite_differences() {
+    let h = 1e-3;
+    for spot in [50., 99., 100.5, 150.] {
+        let (lo, x, hi) = (call(spot - h), call(spot), call(spot + h));
+
+        assert_close(x.delta, (hi.mark - lo.mark) / (2. * h), 1e-5);
+        assert_close(x.gamma, (hi.delta - lo.delta) / (2. * h), 1e-5);
+        assert!(x.mark >= (spot - 100f64).max(0.));
+        assert!((0. ..=1.).contains(&x.delta));
+    }
+}
+
+#[test]
+fn put_call_parity() {
+    for spot in [10., 80., 100., 120., 1_000.] {
+        let c = everlasting_call(spot, 100., &DAILY).unwrap();
+        let p = everlasting_put(spot, 100., &DAILY).unwrap();
+
+        assert_close(c.mark - p.mark, spot - 100., 1e-9);
+        assert_close(c.delta - p.delta, 1., 1e-12);
+        assert_eq!(c.gamma, p.gamma);
+        assert!(p.mark >= (100. - spot).max(0.) - 1e-9);
+    }
+}
+
+#[test]
+fn square_perp_needs_converging_funding() {
+    assert!(square_perp(10., 1., &params(1., 1.)).is_none());
+    assert!(square_perp(10., 1., &params(2., 0.5)).is_none());
+    assert!(square_perp(10., 1., &params(0., 1.)).is_none());
+
+    let x = square_perp(10., 2., &params(1., 0.5)).unwrap();
+    assert_close(x.mark, 100., 1e-12);
+    assert_close(x.delta, 20., 1e-12);
+    assert_close(x.gamma, 2., 1e-12);
+}
+
+#[test]
+fn rejects_invalid_inputs() {
+    assert!(everlasting_call(0., 100., &DAILY).is_none());
+    assert!(everlasting_put(10., -1., &DAILY).is_none());
+    assert!(everlasting_call(10., 100., &params(f64::NAN, 1.)).is_none());
+    assert!(square_perp(f64::INFINITY, 1., &DAILY).is_none());
+}
+
+#[test]
+fn fair_value_by_perp_type() {
+    let f = fair_value(PerpType::Future, 20_000., 0, &DAILY).unwrap();
+    assert_eq!((f.mark, f.delta, f.gamma), (20_000., 1., 0.));
+
+    // Strikes are in smol quote per big asset.
+    let c = fair_value(PerpType::CallOption, 90., 100_000_000, &DAILY);
+    assert_eq!(c, everlasting_call(90., 100., &DAILY));
+    let p = fair_value(PerpType::PutOption, 90., 100_000_000, &DAILY);
+    assert_eq!(p, everlasting_put(90., 100., &DAILY));
+
+    let s = fair_value(PerpType::Square, 10., 2_000_000, &DAILY);
+    assert_eq!(s, square_perp(10., 2., &DAILY));
+    let s = fair_value(PerpType::Square, 10., 0, &DAILY);
+    assert_eq!(s, square_perp(10., 1., &DAILY));
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use common::{key, state, BTC_PERP};
+use zo_abi::{
+    events::*,
+    replay::{MarginEvent, MarginEventKind, Position, Replay, ReplayError},
+};
+
+const ME: u8 = 1;
+const OTHER: u8 = 2;
+
+fn liquidation(
+    liquidation_event: LiquidationEvent,
+    base_symbol: &str,
+    quote_symbol: Option<&str>,
+) -> MarginEvent {
+    MarginEvent::Liquidation(LiquidationLog {
+        liquidation_event,
+        base_symbol: base_symbol.into(),
+        quote_symbol: quote_symbol.map(Into::into),
+        liqor_margin: key(ME),
+        liqee_margin: key(OTHER),
+        assets_to_liqor: 300,
+        quote_to_liqor: -5_000,
+    })
+}
+
+/// Positions and balances of both sides of `event`.
+fn both_sides(event: &MarginEvent) -> [(Position, Vec<(usize, i64)>); 2] {
+    let state = state();
+    [ME, OTHER].map(|m| {
+        let mut r = Replay::new(key(m), &state);
+        assert!(r.apply(1, event).unwrap());
+        let p = r.ledger.positions.get(&0).copied().unwrap_or_default();
+        (p, r.ledger.balances.into_iter().collect())
+    })
+}
+
+#[test]
+fn perp_liquidation_moves_positions() {
+    let e = liquidation(LiquidationEvent::Perp, "BTC-PERP", None);
+    let [(liqor, b0), (liqee, b1)] = both_sides(&e);
+
+    assert_eq!((liqor.size, liqor.quote), (300, -5_000));
+    assert_eq!((liqee.size, liqee.quote), (-300, 5_000));
+    assert!(b0.is_empty() && b1.is_empty());
+}
+
+#[test]
+fn spot_liquidation_moves_balances() {
+    let e = liquidation(LiquidationEvent::Spot, "BTC", Some("USDC"));
+    let [(liqor, b0), (liqee, b1)] = both_sides(&e);
+
+    assert_eq!(b0, [(0, -5_000), (1, 300)]);
+    assert_eq!(b1, [(0, 5_000), (1, -300)]);
+    assert_eq!(liqor, Position::default());
+    assert_eq!(liqee, Position::default());
+
+    // Quote defaults to collateral 0.
+    let e = liquidation(LiquidationEvent::Spot, "BTC", None);
+    let [(_, b0), _] = both_sides(&e);
+    assert_eq!(b0, [(0, -5_000), (1, 300)]);
+}
+
+#[test]
+fn bankruptcy_moves_balances() {
+    let e = MarginEvent::Bankruptcy(BankruptcyLog {
+        base_symbol: "BTC".into(),
+        liqor_margin: key(ME),
+        liqee_margin: key(OTHER),
+        assets_to_liqor: -40,
+        quote_to_liqor: 700,
+        insurance_loss: 0,
+        socialized_loss: 0,
+    });
+    let [(_, b0), (_, b1)] = both_sides(&e);
+
+    assert_eq!(b0, [(0, 700), (1, -40)]);
+    assert_eq!(b1, [(0, -700), (1, 40)]);
+
+    // Bankruptcy in the quote collateral itself.
+    let e = MarginEvent::Bankruptcy(BankruptcyLog {
+        base_symbol: "USDC".into(),
+        liqor_margin: key(ME),
+        liqee_margin: key(OTHER),
+        assets_to_liqor: -40,
+        quote_to_liqor: 700,
+        insurance_loss: 0,
+        socialized_loss: 0,
+    });
+    let [(_, b0), (_, b1)] = both_sides(&e);
+    assert_eq!(b0, [(0, 660)]);
+    assert_eq!(b1, [(0, -660)]);
+}
+
+#[test]
+fn otc_fill_negates_maker() {
+    let e = MarginEvent::OtcFill(OtcFill {
+        market: key(BTC_PERP),
+        taker_margin: key(ME),
+        maker_margin: key(OTHER),
+        d_base: 20,
+        d_quote: -400,
+    });
+    let [(taker, _), (maker, _)] = both_sides(&e);
+
+    assert_eq!((taker.size, taker.quote), (20, -400));
+    assert_eq!((maker.size, maker.quote), (-20, 400));
+}
+
+#[test]
+fn realized_pnl_moves_quote_balance() {
+    let state = state();
+    let mut r = Replay::new(key(ME), &state);
+    let pnl = |pnl| {
+        MarginEvent::RealizedPnl(RealizedPnlLog {
+            market_key: key(BTC_PERP),
+            margin: key(ME),
+            is_long: true,
+            pnl,
+            qty_paid: 0,
+            qty_received: 0,
+        })
+    };
+
+    r.apply_all([
+        (
+            1,
+            MarginEvent::Deposit(DepositLog {
+                col_index: 0,
+                deposit_amount: 1_000,
+                margin_key: key(ME),
+            }),
+        ),
+        (2, pnl(250)),
+        (3, pnl(-100)),
+    ])
+    .unwrap();
+
+    assert_eq!(r.ledger.balances[&0], 1_150);
+    assert_eq!(r.ledger.realized_pnl(), 150);
+    assert_eq!(r.ledger.positions[&0].quote, -150);
+    assert_eq!(r.history.len(), 3);
+    assert_eq!(r.history[1].kind, MarginEventKind::RealizedPnl);
+    assert_eq!(r.history[1].ledger.balances[&0], 1_250);
+}
+
+#[test]
+fn skips_other_margins_and_rejects_unknown_markets() {
+    let state = state();
+    let mut r = Replay::new(key(7), &state);
+    let e = liquidation(LiquidationEvent::Perp, "BTC-PERP", None);
+
+    assert!(!r.apply(1, &e).unwrap());
+    assert!(r.history.is_empty());
+
+    let mut r = Replay::new(key(ME), &state);
+    let e = liquidation(LiquidationEvent::Perp, "ETH-PERP", None);
+    assert_eq!(
+        r.apply(1, &e),
+        Err(ReplayError::UnknownMarket("ETH-PERP".into()))
+    );
+}
+
+#[test]
+fn fills_and_realized_pnl_round_trip() {
+    let state = state();
+    let mut r = Replay::new(key(ME), &state);
+    let fill = |is_long, is_maker, qty_paid, qty_received, fee_or_rebate| {
+        MarginEvent::Fill(EventFillLog {
+            is_long,
+            is_maker,
+            market_key: key(BTC_PERP),
+            margin: key(ME),
+            control: key(5),
+            qty_paid,
+            qty_received,
+            fee_or_rebate,
+            discriminator: 0,
+        })
+    };
+
+    // Taker buys 10 at 1_000_000 paying 100 of fees, then maker sells
+    // 10 at 1_100_000 earning 50 of rebates.
+    r.apply(1, &fill(true, false, 1_000_100, 10, 100)).unwrap();
+    r.apply(2, &fill(false, true, 10, 1_100_050, 50)).unwrap();
+
+    let p = r.ledger.positions[&0];
+    assert_eq!((p.size, p.quote, p.fees), (0, 99_950, 50));
+    assert!(r.ledger.balances.is_empty());
+
+    r.apply(
+        3,
+        &MarginEvent::RealizedPnl(RealizedPnlLog {
+            market_key: key(BTC_PERP),
+            margin: key(ME),
+            is_long: false,
+            pnl: 99_950,
+            qty_paid: 10,
+            qty_received: 1_100_050,
+        }),
+    )
+    .unwrap();
+
+    let p = r.ledger.positions[&0];
+    assert_eq!((p.size, p.quote, p.realized_pnl), (0, 0, 99_950));
+    assert_eq!(r.ledger.balances[&0], 99_950);
+    assert_eq!(r.ledger.fees(), 50);
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use bytemuck::Zeroable;
+use common::key;
+use zo_abi::{
+    dex::{Side, ZoDexMarket},
+    snapshot::{SnapshotError, ZoSnapshot},
+    test_utils::*,
+    PerpType,
+};
+
+const STATE: u8 = 1;
+const CACHE: u8 = 2;
+const MARGIN: u8 = 3;
+const CONTROL: u8 = 4;
+const AUTHORITY: u8 = 5;
+const MARKET: u8 = 6;
+const BIDS: u8 = 7;
+const ASKS: u8 = 8;
+
+fn accounts(bids: Vec<u8>) -> Vec<(Pubkey, Vec<u8>)> {
+    let state = StateBuilder::new()
+        .cache(key(CACHE))
+        .add_collateral(key(20), "USDC", 6, 1000)
+        .add_perp_market(
+            "BTC-PERP",
+            "BTC",
+            PerpType::Future,
+            6,
+            100,
+            10,
+            key(MARKET),
+        )
+        .build_bytes();
+
+    let mut market = ZoDexMarket::zeroed();
+    market.own_address = key(MARKET);
+    market.bids = key(BIDS);
+    market.asks = key(ASKS);
+
+    vec![
+        (key(STATE), state),
+        (key(CACHE), CacheBuilder::new().build_bytes()),
+        (
+            key(MARGIN),
+            MarginBuilder::new(key(AUTHORITY), key(CONTROL)).build_bytes(),
+        ),
+        (
+            key(CONTROL),
+            ControlBuilder::new(key(AUTHORITY)).build_bytes(),
+        ),
+        (key(MARKET), bytemuck::bytes_of(&market).to_vec()),
+        (key(BIDS), bids),
+    ]
+}
+
+fn bids() -> Vec<u8> {
+    SlabBuilder::new(Side::Bid)
+        .add_order(100, 1, 5, key(CONTROL), 0)
+        .add_order(99, 2, 5, key(CONTROL), 0)
+        .build_bytes()
+}
+
+#[test]
+fn loads_books() {
+    let s = ZoSnapshot::new(key(STATE), key(MARGIN), accounts(bids())).unwrap();
+
+    let book = s.book(0, Side::Bid).unwrap();
+    assert_eq!(book.get_best().unwrap().price().get(), 100);
+    assert!(s.book(0, Side::Ask).is_none());
+}
+
+#[test]
+fn rejects_invalid_books() {
+    // Bad padding.
+    let mut buf = bids();
+    let n = buf.len();
+    buf[n - 1] = 0;
+    assert_eq!(
+        ZoSnapshot::new(key(STATE), key(MARGIN), accounts(buf)).unwrap_err(),
+        SnapshotError::InvalidAccount(key(BIDS))
+    );
+
+    // Unknown node tag.
+    let mut buf = bids();
+    buf[45] = 9;
+    assert_eq!(
+        ZoSnapshot::new(key(STATE), key(MARGIN), accounts(buf)).unwrap_err(),
+        SnapshotError::InvalidAccount(key(BIDS))
+    );
+
+    // Too short to hold a header.
+    assert_eq!(
+        ZoSnapshot::new(key(STATE), key(MARGIN), accounts(vec![0; 10]))
+            .unwrap_err(),
+        SnapshotError::InvalidAccount(key(BIDS))
+    );
+}
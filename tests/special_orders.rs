@@ -0,0 +1,162 @@
+use anchor_lang::prelude::Pubkey;
+use bytemuck::Zeroable;
+use zo_abi::{
+    dex::ZoDexMarket,
+    special_orders::{
+        validate_special_order, SpecialOrderError, SpecialOrderParams,
+        SpecialOrderWarning,
+    },
+    SpecialOrderType, SpecialOrders, MAX_SPECIAL_ORDERS,
+};
+
+use SpecialOrderType::*;
+
+/// One price lot is 10 USDC, 10_000_000 smol per big.
+fn market() -> ZoDexMarket {
+    let mut m = ZoDexMarket::zeroed();
+    m.own_address = Pubkey::new_from_array([1; 32]);
+    m.coin_decimals = 8;
+    m.coin_lot_size = 100;
+    m.pc_lot_size = 10;
+    m
+}
+
+const MARK: u64 = 20_000_000_000;
+
+/// Closes a long at 21_000.
+fn take_profit() -> SpecialOrderParams {
+    SpecialOrderParams {
+        is_long: false,
+        ty: TakeProfitMarket,
+        trigger_price: 21_000_000_000,
+        limit_price: 0,
+        size: 5,
+    }
+}
+
+fn orders(ids: impl IntoIterator<Item = u16>, prev_id: u16) -> SpecialOrders {
+    let mut r = SpecialOrders::zeroed();
+    for (x, id) in r.entries.iter_mut().zip(ids) {
+        x.id = id;
+        x.size = 1;
+    }
+    r.prev_id = prev_id;
+    r
+}
+
+fn validate(
+    params: &SpecialOrderParams,
+) -> Result<Vec<SpecialOrderWarning>, SpecialOrderError> {
+    validate_special_order(params, &market(), &orders([], 0), MARK)
+        .map(|x| x.warnings)
+}
+
+#[test]
+fn accepts_orders_on_lots() {
+    let r =
+        validate_special_order(&take_profit(), &market(), &orders([], 0), MARK);
+
+    assert_eq!(r.map(|x| (x.next_id, x.warnings)), Ok((1, vec![])));
+
+    let stop = SpecialOrderParams {
+        ty: StopLossLimit,
+        trigger_price: 19_000_000_000,
+        limit_price: 18_990_000_000,
+        ..take_profit()
+    };
+    assert_eq!(validate(&stop), Ok(vec![]));
+}
+
+#[test]
+fn rejects_triggers_on_the_wrong_side() {
+    // Taking profit on a short above the mark.
+    let p = SpecialOrderParams {
+        is_long: true,
+        ..take_profit()
+    };
+    assert_eq!(validate(&p), Err(SpecialOrderError::TriggersImmediately));
+
+    // Stopping a long's loss above the mark.
+    let p = SpecialOrderParams {
+        ty: StopLossMarket,
+        ..take_profit()
+    };
+    assert_eq!(validate(&p), Err(SpecialOrderError::TriggersImmediately));
+
+    let p = SpecialOrderParams {
+        trigger_price: MARK,
+        ..take_profit()
+    };
+    assert_eq!(validate(&p), Err(SpecialOrderError::TriggersImmediately));
+}
+
+#[test]
+fn checks_prices_and_size() {
+    let p = SpecialOrderParams {
+        size: 0,
+        ..take_profit()
+    };
+    assert_eq!(validate(&p), Err(SpecialOrderError::ZeroSize));
+
+    let p = SpecialOrderParams {
+        trigger_price: 9_999_999,
+        ..take_profit()
+    };
+    assert_eq!(validate(&p), Err(SpecialOrderError::PriceBelowLot));
+
+    let p = SpecialOrderParams {
+        ty: TakeProfitLimit,
+        ..take_profit()
+    };
+    assert_eq!(validate(&p), Err(SpecialOrderError::MissingLimitPrice));
+}
+
+#[test]
+fn warns_about_ignored_and_off_lot_prices() {
+    let p = SpecialOrderParams {
+        limit_price: 21_000_000_000,
+        ..take_profit()
+    };
+    assert_eq!(
+        validate(&p),
+        Ok(vec![SpecialOrderWarning::LimitPriceOnMarketOrder])
+    );
+
+    let p = SpecialOrderParams {
+        ty: TakeProfitLimit,
+        trigger_price: 21_000_000_001,
+        limit_price: 20_995_000_000,
+        ..take_profit()
+    };
+    assert_eq!(
+        validate(&p),
+        Ok(vec![
+            SpecialOrderWarning::TriggerPriceNotOnLot,
+            SpecialOrderWarning::LimitPriceNotOnLot,
+        ])
+    );
+}
+
+#[test]
+fn rejects_full_accounts() {
+    let full = orders(1..=MAX_SPECIAL_ORDERS as u16, 20);
+    let r = validate_special_order(&take_profit(), &market(), &full, MARK);
+
+    assert_eq!(r, Err(SpecialOrderError::Full));
+}
+
+#[test]
+fn next_id_skips_zero_and_used_ids() {
+    let check = |ids: Vec<u16>, prev_id| {
+        let o = orders(ids, prev_id);
+        validate_special_order(&take_profit(), &market(), &o, MARK)
+            .unwrap()
+            .next_id
+    };
+
+    assert_eq!(check(vec![7], 7), 8);
+    assert_eq!(check(vec![8, 9], 7), 10);
+    assert_eq!(check(vec![u16::MAX], u16::MAX), 1);
+    assert_eq!(check(vec![1, 2], u16::MAX - 1), u16::MAX);
+    assert_eq!(check(vec![u16::MAX, 1, 2], u16::MAX - 1), 3);
+}
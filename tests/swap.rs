@@ -0,0 +1,135 @@
+mod common;
+
+use bytemuck::Zeroable;
+use common::{key, state_builder, BTC_MINT, USDC_MINT};
+use zo_abi::{
+    dex::{Side, Slab},
+    serum::SerumMarket,
+    swap::{quote_swap, SwapParams, SwapQuote, SwapQuoteError},
+    test_utils::*,
+    State,
+};
+
+fn state() -> State {
+    state_builder().swappable(key(BTC_MINT), key(4)).build()
+}
+
+/// One lot is 100 smol BTC, and a price of 200_000 lots is 20_000 USDC.
+fn market() -> SerumMarket {
+    let mut m = SerumMarket::zeroed();
+    m.coin_mint = key(BTC_MINT);
+    m.pc_mint = key(USDC_MINT);
+    m.coin_lot_size = 100;
+    m.pc_lot_size = 10;
+    m
+}
+
+fn book(side: Side, levels: &[(u64, u64)]) -> Vec<u8> {
+    levels
+        .iter()
+        .enumerate()
+        .fold(SlabBuilder::new(side), |b, (i, (price, qty))| {
+            b.add_order(*price, i as u64, *qty, key(9), 0)
+        })
+        .build_bytes()
+}
+
+fn quote(
+    buf: &[u8],
+    buy: bool,
+    amount: u64,
+    fee_bps: u64,
+) -> Result<SwapQuote, SwapQuoteError> {
+    let params = SwapParams {
+        buy,
+        amount,
+        fee_bps,
+        slippage_bps: 100,
+    };
+    quote_swap(
+        &state(),
+        &market(),
+        &Slab::deserialize(buf).unwrap(),
+        params,
+    )
+}
+
+#[test]
+fn buy_across_levels() {
+    let asks = book(Side::Ask, &[(200_000, 5), (201_000, 5)]);
+    let q = quote(&asks, true, 12_010_000, 0).unwrap();
+
+    // 5 lots at 2 USDC and 1 at 2.01 USDC.
+    assert_eq!(q.amount_in, 12_010_000);
+    assert_eq!(q.amount_out, 600);
+    assert_eq!(q.fee, 0);
+    assert_eq!(q.price_impact_bps, 8);
+    // 594 smol BTC per 12.01 USDC.
+    assert_eq!(q.min_rate, 49);
+}
+
+#[test]
+fn buy_charges_fee_on_top() {
+    let asks = book(Side::Ask, &[(200_000, 5)]);
+    let q = quote(&asks, true, 4_004_000, 10).unwrap();
+
+    assert_eq!(q.amount_in, 4_004_000);
+    assert_eq!(q.amount_out, 200);
+    assert_eq!(q.fee, 4_000);
+}
+
+#[test]
+fn buy_rounding_does_not_loosen_min_rate() {
+    let asks = book(Side::Ask, &[(200_000, 5)]);
+    let q = quote(&asks, true, 3_000_000, 0).unwrap();
+
+    // 1 USDC is left over, but the rate is that of the lot bought.
+    assert_eq!(q.amount_in, 2_000_000);
+    assert_eq!(q.amount_out, 100);
+    assert_eq!(q.min_rate, 49);
+}
+
+#[test]
+fn buy_beyond_book_fails() {
+    let asks = book(Side::Ask, &[(200_000, 5), (201_000, 5)]);
+
+    assert_eq!(
+        quote(&asks, true, 100_000_000, 0),
+        Err(SwapQuoteError::InsufficientLiquidity)
+    );
+    // The whole book, with less than a lot left over.
+    assert!(quote(&asks, true, 21_000_000, 0).is_ok());
+}
+
+#[test]
+fn sell_across_levels() {
+    let bids = book(Side::Bid, &[(200_000, 4), (190_000, 6)]);
+    let q = quote(&bids, false, 1_050, 10).unwrap();
+
+    // 4 lots at 2 USDC and 6 at 1.9 USDC, the rest is less than a lot.
+    assert_eq!(q.amount_in, 1_000);
+    assert_eq!(q.fee, 19_400);
+    assert_eq!(q.amount_out, 19_380_600);
+    // 19.186794 USDC per 0.001 BTC.
+    assert_eq!(q.min_rate, 19_186_794_000);
+}
+
+#[test]
+fn sell_beyond_book_fails() {
+    let bids = book(Side::Bid, &[(200_000, 10)]);
+
+    assert_eq!(
+        quote(&bids, false, 1_000_000, 0),
+        Err(SwapQuoteError::InsufficientLiquidity)
+    );
+}
+
+#[test]
+fn rejects_wrong_side() {
+    let bids = book(Side::Bid, &[(200_000, 10)]);
+
+    assert_eq!(
+        quote(&bids, true, 1_000_000, 0),
+        Err(SwapQuoteError::WrongBookSide)
+    );
+}
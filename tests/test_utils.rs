@@ -0,0 +1,181 @@
+mod common;
+
+use anchor_lang::Discriminator;
+use bytemuck::Pod;
+use common::key;
+use fixed::types::I80F48;
+use std::mem::size_of;
+use zo_abi::{
+    dex::{Side, Slab},
+    test_utils::*,
+    Cache, Control, Margin, PerpType, State, Symbol,
+};
+
+fn from_account_bytes<T: Discriminator + Pod>(buf: &[u8]) -> &T {
+    assert_eq!(buf.len(), 8 + size_of::<T>());
+    assert_eq!(buf[..8], T::discriminator());
+    bytemuck::from_bytes(&buf[8..])
+}
+
+fn sym(s: &str) -> Symbol {
+    Symbol::try_from(s).unwrap()
+}
+
+fn oracle_price(cache: &Cache, s: &str) -> Option<I80F48> {
+    cache.get_oracle(&sym(s)).map(|o| o.price.into())
+}
+
+#[test]
+fn state_round_trips() {
+    let buf = StateBuilder::new()
+        .admin(key(1))
+        .cache(key(2))
+        .add_collateral(key(3), "USDC", 6, 1000)
+        .add_collateral(key(4), "BTC", 6, 900)
+        .swappable(key(4), key(5))
+        .add_perp_market(
+            "BTC-PERP",
+            "BTC",
+            PerpType::Future,
+            6,
+            100,
+            10,
+            key(6),
+        )
+        .market_params(0, 50)
+        .build_bytes();
+    let state: &State = from_account_bytes(&buf);
+
+    assert_eq!({ state.admin }, key(1));
+    assert_eq!({ state.cache }, key(2));
+    assert_eq!({ state.total_collaterals }, 2);
+    assert_eq!({ state.total_markets }, 1);
+
+    let btc = &state.collaterals[1];
+    assert_eq!(btc.oracle_symbol, sym("BTC"));
+    assert_eq!({ btc.weight }, 900);
+    assert!(btc.is_swappable);
+    assert_eq!({ btc.serum_open_orders }, key(5));
+
+    let m = &state.perp_markets[0];
+    assert_eq!(m.symbol, sym("BTC-PERP"));
+    assert_eq!({ m.asset_lot_size }, 100);
+    assert_eq!({ m.base_imf }, 50);
+    assert_eq!({ m.dex_market }, key(6));
+}
+
+#[test]
+fn cache_keeps_oracles_sorted() {
+    let buf = CacheBuilder::new()
+        .updated_at(1_000)
+        .set_oracle_price("SOL", I80F48::from_num(30), 9, 6)
+        .set_oracle_price("BTC", I80F48::from_num(20_000), 6, 6)
+        .set_oracle_price("USDC", I80F48::ONE, 6, 6)
+        .set_oracle_price("ETH", I80F48::from_num(1_500), 6, 6)
+        .updated_at(2_000)
+        .set_oracle_price("BTC", I80F48::from_num(21_000), 6, 6)
+        .build_bytes();
+    let cache: &Cache = from_account_bytes(&buf);
+
+    assert_eq!(oracle_price(cache, "SOL"), Some(I80F48::from_num(30)));
+    assert_eq!(oracle_price(cache, "BTC"), Some(I80F48::from_num(21_000)));
+    assert_eq!(oracle_price(cache, "USDC"), Some(I80F48::ONE));
+    assert_eq!(oracle_price(cache, "ETH"), Some(I80F48::from_num(1_500)));
+    assert_eq!(oracle_price(cache, "DOGE"), None);
+
+    let n = cache.oracles.iter().filter(|o| !o.symbol.is_nil()).count();
+    assert_eq!(n, 4);
+
+    let btc = cache.get_oracle(&sym("BTC")).unwrap();
+    assert_eq!({ btc.last_updated }, 2_000);
+    assert_eq!({ btc.base_decimals }, 6);
+    let sol = cache.get_oracle(&sym("SOL")).unwrap();
+    assert_eq!({ sol.last_updated }, 1_000);
+    assert_eq!({ sol.base_decimals }, 9);
+}
+
+#[test]
+fn cache_starts_with_unit_multipliers() {
+    let buf = CacheBuilder::new()
+        .set_mark_price(1, I80F48::from_num(25))
+        .set_funding(1, -7)
+        .build_bytes();
+    let cache: &Cache = from_account_bytes(&buf);
+
+    let b = &cache.borrow_cache[3];
+    assert_eq!(I80F48::from(b.supply_multiplier), I80F48::ONE);
+    assert_eq!(I80F48::from(b.borrow_multiplier), I80F48::ONE);
+    assert_eq!(I80F48::from(cache.marks[1].price), I80F48::from_num(25));
+    assert_eq!({ cache.funding_cache }[1], -7);
+}
+
+#[test]
+fn margin_and_control_round_trip() {
+    let buf = MarginBuilder::new(key(1), key(2))
+        .nonce(254)
+        .set_collateral(0, I80F48::from_num(1_000_000))
+        .build_bytes();
+    let margin: &Margin = from_account_bytes(&buf);
+
+    assert_eq!({ margin.authority }, key(1));
+    assert_eq!({ margin.control }, key(2));
+    assert_eq!(margin.nonce, 254);
+    assert_eq!(
+        I80F48::from({ margin.collateral }[0]),
+        I80F48::from_num(1_000_000)
+    );
+
+    let buf = ControlBuilder::new(key(1))
+        .set_position(2, key(3), -500, 1_000)
+        .set_orders(2, 100, 200, 3)
+        .build_bytes();
+    let control: &Control = from_account_bytes(&buf);
+    let oo = &control.open_orders_agg[2];
+
+    assert_eq!({ control.authority }, key(1));
+    assert_eq!({ oo.key }, key(3));
+    assert_eq!({ oo.pos_size }, -500);
+    assert_eq!({ oo.native_pc_total }, 1_000);
+    assert_eq!({ oo.coin_on_bids }, 100);
+    assert_eq!({ oo.coin_on_asks }, 200);
+    assert_eq!(oo.order_count, 3);
+}
+
+fn prices(slab: &Slab) -> Vec<(u64, u64)> {
+    slab.iter_best()
+        .map(|x| (x.price().get(), { x.quantity }))
+        .collect()
+}
+
+#[test]
+fn slab_iterates_best_first() {
+    let asks = SlabBuilder::new(Side::Ask)
+        .add_order(105, 3, 1, key(1), 0)
+        .add_order(100, 2, 2, key(1), 0)
+        .add_order(110, 1, 3, key(2), 0)
+        .add_order(100, 1, 4, key(2), 0)
+        .build_bytes();
+    let asks = Slab::deserialize(&asks).unwrap();
+
+    assert!(asks.is_asks());
+    // Older orders first at the same price.
+    assert_eq!(prices(&asks), [(100, 4), (100, 2), (105, 1), (110, 3)]);
+
+    let bids = SlabBuilder::new(Side::Bid)
+        .add_order(95, 1, 1, key(1), 0)
+        .add_order(99, 2, 2, key(1), 0)
+        .add_order(99, 3, 3, key(2), 0)
+        .build_bytes();
+    let bids = Slab::deserialize(&bids).unwrap();
+
+    assert!(bids.is_bids());
+    assert_eq!(prices(&bids), [(99, 2), (99, 3), (95, 1)]);
+}
+
+#[test]
+fn empty_slab() {
+    let buf = SlabBuilder::new(Side::Bid).build_bytes();
+    let slab = Slab::deserialize(&buf).unwrap();
+
+    assert!(slab.get_best().is_none());
+}
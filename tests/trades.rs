@@ -0,0 +1,158 @@
+mod common;
+
+use bytemuck::Zeroable;
+use common::key;
+use zo_abi::{
+    dex::{Side, ZoDexMarket},
+    events::EventFillLog,
+    test_utils::*,
+    trades::{Candles, Trade, TradeTape},
+};
+
+fn market() -> ZoDexMarket {
+    let mut m = ZoDexMarket::zeroed();
+    m.own_address = key(100);
+    m.coin_decimals = 8;
+    m
+}
+
+/// A match of `coin` smol asset for `pc` smol quote, as the maker's and
+/// taker's fills.
+fn fill(
+    q: EventQueueBuilder,
+    maker_is_bid: bool,
+    coin: u64,
+    pc: u64,
+) -> EventQueueBuilder {
+    let (maker, taker) = (key(1), key(2));
+    match maker_is_bid {
+        true => q
+            .push_fill(true, true, maker, coin, pc)
+            .push_fill(false, false, taker, pc, coin),
+        false => q
+            .push_fill(true, false, maker, pc, coin)
+            .push_fill(false, true, taker, coin, pc),
+    }
+}
+
+fn summary(trades: &[Trade]) -> Vec<(f64, f64, Side, u64)> {
+    trades
+        .iter()
+        .map(|t| (t.price, t.size, t.side, t.timestamp))
+        .collect()
+}
+
+#[test]
+fn takes_maker_fills_with_taker_side() {
+    // 0.5 BTC at 20_000, sold into a bid, then 0.25 at 20_100 bought.
+    let q = fill(EventQueueBuilder::new(8), true, 50_000_000, 10_000_000_000);
+    let q = fill(q, false, 25_000_000, 5_025_000_000).push_out(key(1));
+
+    let mut tape = TradeTape::default();
+    let trades = tape
+        .push_event_queue(&market(), &q.build_bytes(), 7)
+        .unwrap();
+
+    assert_eq!(
+        summary(trades),
+        [
+            (20_000.0, 0.5, Side::Ask, 7),
+            (20_100.0, 0.25, Side::Bid, 7)
+        ]
+    );
+    assert!(trades.iter().all(|t| t.market == key(100)));
+}
+
+#[test]
+fn polls_only_new_events() {
+    let m = market();
+    // Events consumed before the first poll are not seen again.
+    let q = fill(EventQueueBuilder::new(6), true, 100_000_000, 20_000_000_000)
+        .consume(2);
+    let q = fill(q, true, 100_000_000, 21_000_000_000);
+
+    let mut tape = TradeTape::default();
+    let first =
+        summary(tape.push_event_queue(&m, &q.build_bytes(), 1).unwrap());
+    assert_eq!(first, [(21_000.0, 1.0, Side::Ask, 1)]);
+
+    let again = tape.push_event_queue(&m, &q.build_bytes(), 2).unwrap();
+    assert!(again.is_empty());
+
+    // Wraps around the ring buffer.
+    let q = fill(q.consume(2), false, 100_000_000, 22_000_000_000);
+    let q = fill(q.consume(2), false, 100_000_000, 23_000_000_000);
+    let next = summary(tape.push_event_queue(&m, &q.build_bytes(), 3).unwrap());
+    assert_eq!(
+        next,
+        [(22_000.0, 1.0, Side::Bid, 3), (23_000.0, 1.0, Side::Bid, 3)]
+    );
+    assert_eq!(tape.trades.len(), 3);
+}
+
+#[test]
+fn takes_maker_fill_logs() {
+    let mut log = EventFillLog {
+        is_long: false,
+        is_maker: true,
+        market_key: key(100),
+        margin: key(3),
+        control: key(1),
+        qty_paid: 10_000_000,
+        qty_received: 2_000_000_000,
+        fee_or_rebate: 0,
+        discriminator: 0,
+    };
+    let mut tape = TradeTape::default();
+
+    let t = *tape.push_fill_log(&market(), &log, 5).unwrap();
+    assert_eq!(summary(&[t]), [(20_000.0, 0.1, Side::Bid, 5)]);
+
+    log.is_maker = false;
+    assert!(tape.push_fill_log(&market(), &log, 5).is_none());
+}
+
+fn trade(market: u8, price: f64, size: f64, timestamp: u64) -> Trade {
+    Trade {
+        market: key(market),
+        price,
+        size,
+        side: Side::Bid,
+        timestamp,
+    }
+}
+
+#[test]
+fn candles_bucket_by_interval() {
+    let trades = [
+        trade(1, 10.0, 1.0, 59),
+        trade(1, 12.0, 1.0, 60),
+        trade(1, 9.0, 2.0, 100),
+        trade(1, 11.0, 1.0, 119),
+        trade(2, 50.0, 1.0, 60),
+        trade(1, 13.0, 1.0, 120),
+    ];
+    let candles = Candles::from_trades(60, &trades);
+
+    let c = candles.get(&key(1));
+    let starts: Vec<_> = c.iter().map(|c| c.start).collect();
+    assert_eq!(starts, [0, 60, 120]);
+
+    let c = c[1];
+    assert_eq!((c.open, c.high, c.low, c.close), (12.0, 12.0, 9.0, 11.0));
+    assert_eq!((c.volume, c.quote_volume, c.trades), (4.0, 41.0, 3));
+
+    assert_eq!(candles.get(&key(2)).len(), 1);
+    assert!(candles.get(&key(3)).is_empty());
+    assert_eq!(candles.markets().count(), 2);
+}
+
+#[test]
+fn candles_accept_late_buckets() {
+    let mut candles = Candles::new(60);
+    candles.push(&trade(1, 10.0, 1.0, 200));
+    candles.push(&trade(1, 11.0, 1.0, 30));
+
+    let starts: Vec<_> = candles.get(&key(1)).iter().map(|c| c.start).collect();
+    assert_eq!(starts, [0, 180]);
+}
@@ -0,0 +1,88 @@
+use fixed::types::I80F48;
+use zo_abi::{
+    twap::{replay_twap, update_twap},
+    TwapInfo,
+};
+
+// 2022-08-03 00:00:00 UTC, the start of a funding period.
+const HOUR: u64 = 1_659_484_800;
+
+fn p(x: i32) -> I80F48 {
+    I80F48::from_num(x)
+}
+
+fn ohlc(t: &TwapInfo) -> [I80F48; 4] {
+    [t.open.into(), t.high.into(), t.low.into(), t.close.into()]
+}
+
+fn cumul_avg(t: &TwapInfo) -> I80F48 {
+    t.cumul_avg.into()
+}
+
+#[test]
+fn first_sample_initializes() {
+    let t = replay_twap([(HOUR + 1_000, p(100))]);
+
+    assert_eq!({ t.last_sample_start_time }, HOUR + 900);
+    assert_eq!(ohlc(&t), [p(100); 4]);
+    assert_eq!(cumul_avg(&t), p(100));
+}
+
+#[test]
+fn samples_within_slot_update_ohlc() {
+    let t = replay_twap([
+        (HOUR, p(100)),
+        (HOUR + 60, p(120)),
+        (HOUR + 120, p(90)),
+        (HOUR + 299, p(110)),
+    ]);
+
+    assert_eq!({ t.last_sample_start_time }, HOUR);
+    assert_eq!(ohlc(&t), [p(100), p(120), p(90), p(110)]);
+    // Only the close of the current slot counts.
+    assert_eq!(cumul_avg(&t), p(110));
+}
+
+#[test]
+fn averages_slot_closes() {
+    let t = replay_twap([
+        (HOUR, p(100)),
+        (HOUR + 300, p(100)),
+        (HOUR + 400, p(110)),
+        (HOUR + 600, p(120)),
+    ]);
+
+    assert_eq!({ t.last_sample_start_time }, HOUR + 600);
+    assert_eq!(ohlc(&t), [p(120); 4]);
+    assert_eq!(cumul_avg(&t), p(110));
+}
+
+#[test]
+fn missed_slots_repeat_close() {
+    let t = replay_twap([(HOUR, p(100)), (HOUR + 900, p(140))]);
+
+    // Slots 1 and 2 count as 100.
+    assert_eq!(cumul_avg(&t), p(110));
+}
+
+#[test]
+fn new_funding_period_resets_average() {
+    let t = replay_twap([
+        (HOUR + 3_300, p(200)),
+        (HOUR + 3_500, p(100)),
+        (HOUR + 3_600 + 600, p(130)),
+    ]);
+
+    assert_eq!({ t.last_sample_start_time }, HOUR + 4_200);
+    // Slots 0 and 1 of the new period count as 100.
+    assert_eq!(cumul_avg(&t), p(110));
+}
+
+#[test]
+fn ignores_samples_of_past_slots() {
+    let t = replay_twap([(HOUR + 600, p(100))]);
+    let u = update_twap(&t, p(500), HOUR + 299);
+
+    assert_eq!(ohlc(&u), ohlc(&t));
+    assert_eq!(cumul_avg(&u), cumul_avg(&t));
+}